@@ -0,0 +1,518 @@
+use crate::bigint::BigInt;
+use crate::complex::Complex;
+use crate::datetime;
+use crate::decimal::Decimal;
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::rational::Rational;
+use crate::settings::{DivisionMode, Settings};
+use crate::span::Span;
+use crate::units;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+/// The largest integer `power` computes, in bits, which is about 75,000
+/// decimal digits. Larger ones would take seconds to compute.
+const MAX_POWER_BITS: u64 = 250_000;
+
+enum Operands {
+    Int(i64, i64),
+    Big(BigInt, BigInt),
+    Rational(Rational, Rational),
+    Decimal(Decimal, Decimal),
+    Float(f64, f64),
+    Complex(Complex, Complex),
+}
+
+fn coerce(left: &Object, right: &Object) -> Option<Operands> {
+    match (left, right) {
+        (Object::Int(left), Object::Int(right)) => Some(Operands::Int(*left, *right)),
+        (Object::Complex(_), _) | (_, Object::Complex(_)) => {
+            Some(Operands::Complex(to_complex(left)?, to_complex(right)?))
+        }
+        (Object::Float(left), Object::Float(right)) => Some(Operands::Float(*left, *right)),
+        (Object::Float(left), right) => Some(Operands::Float(*left, to_float(right)?)),
+        (left, Object::Float(right)) => Some(Operands::Float(to_float(left)?, *right)),
+        (Object::Rational(_), _) | (_, Object::Rational(_)) => {
+            Some(Operands::Rational(to_rational(left)?, to_rational(right)?))
+        }
+        (Object::Decimal(_), _) | (_, Object::Decimal(_)) => {
+            Some(Operands::Decimal(to_decimal(left)?, to_decimal(right)?))
+        }
+        (left, right) => Some(Operands::Big(to_big(left)?, to_big(right)?)),
+    }
+}
+
+fn to_big(value: &Object) -> Option<BigInt> {
+    match value {
+        Object::Int(value) => Some(BigInt::from(*value)),
+        Object::BigInt(value) => Some((**value).clone()),
+        _ => None,
+    }
+}
+
+fn to_rational(value: &Object) -> Option<Rational> {
+    match value {
+        Object::Rational(value) => Some((**value).clone()),
+        Object::Decimal(value) => Some(value.to_rational()),
+        value => Some(Rational::from_integer(to_big(value)?)),
+    }
+}
+
+fn to_decimal(value: &Object) -> Option<Decimal> {
+    match value {
+        Object::Decimal(value) => Some((**value).clone()),
+        value => Some(Decimal::from_integer(to_big(value)?)),
+    }
+}
+
+fn to_complex(value: &Object) -> Option<Complex> {
+    match value {
+        Object::Complex(value) => Some(*value),
+        value => Some(Complex::new(to_float(value)?, 0.0)),
+    }
+}
+
+pub fn to_float(value: &Object) -> Option<f64> {
+    match value {
+        Object::Int(value) => Some(*value as f64),
+        Object::BigInt(value) => Some(value.to_f64()),
+        Object::Rational(value) => Some(value.to_f64()),
+        Object::Decimal(value) => Some(value.to_f64()),
+        Object::Float(value) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Wraps an integer result, using the machine representation when it fits.
+pub fn integer(value: BigInt) -> Object {
+    match value.to_i64() {
+        Some(value) => Object::Int(value),
+        None => Object::BigInt(Rc::new(value)),
+    }
+}
+
+/// Wraps a fractional result, collapsing it to an integer when it is whole.
+pub fn rational(value: Rational) -> Object {
+    if value.is_integer() {
+        integer(value.numerator)
+    } else {
+        Object::Rational(Rc::new(value))
+    }
+}
+
+pub fn decimal(value: Decimal) -> Object {
+    Object::Decimal(Rc::new(value))
+}
+
+pub fn is_number(value: &Object) -> bool {
+    matches!(
+        value,
+        Object::Int(_)
+            | Object::BigInt(_)
+            | Object::Rational(_)
+            | Object::Decimal(_)
+            | Object::Float(_)
+            | Object::Complex(_)
+    )
+}
+
+fn is_integer(value: &Object) -> bool {
+    matches!(value, Object::Int(_) | Object::BigInt(_))
+}
+
+pub fn is_zero(value: &Object) -> bool {
+    match value {
+        Object::Int(value) => *value == 0,
+        Object::BigInt(value) => value.is_zero(),
+        Object::Rational(value) => value.is_zero(),
+        Object::Decimal(value) => value.is_zero(),
+        Object::Float(value) => *value == 0.0,
+        Object::Complex(value) => value.is_zero(),
+        _ => false,
+    }
+}
+
+pub fn negate(value: Object, span: &Span) -> Result<Object, QclError> {
+    match value {
+        Object::Int(value) => Ok(value
+            .checked_neg()
+            .map(Object::Int)
+            .unwrap_or_else(|| integer(-&BigInt::from(value)))),
+        Object::BigInt(value) => Ok(integer(-&*value)),
+        Object::Rational(value) => Ok(rational(value.negate())),
+        Object::Decimal(value) => Ok(decimal(value.negate())),
+        Object::Float(value) => Ok(Object::Float(-value)),
+        Object::Complex(value) => Ok(Object::Complex(value.negate())),
+        Object::Quantity(value) => Ok(units::negate(&value)),
+        Object::Percentage(value) => Ok(Object::Percentage(Box::new(negate(*value, span)?))),
+        value => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot negate {}", value.type_name()),
+        )),
+    }
+}
+
+pub fn add(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if let (Object::Str(left), Object::Str(right)) = (&left, &right) {
+        return Ok(Object::Str(format!("{}{}", left, right).into()));
+    }
+    if datetime::is_datetime(&left) || datetime::is_datetime(&right) {
+        return datetime::add(left, right, span);
+    }
+    if units::is_quantity(&left) || units::is_quantity(&right) {
+        return units::add(left, right, span);
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => Ok(left
+            .checked_add(right)
+            .map(Object::Int)
+            .unwrap_or_else(|| integer(&BigInt::from(left) + &BigInt::from(right)))),
+        Some(Operands::Big(left, right)) => Ok(integer(&left + &right)),
+        Some(Operands::Rational(left, right)) => Ok(rational(left.add(&right))),
+        Some(Operands::Decimal(left, right)) => Ok(decimal(left.add(&right))),
+        Some(Operands::Float(left, right)) => Ok(Object::Float(left + right)),
+        Some(Operands::Complex(left, right)) => Ok(Object::Complex(left.add(&right))),
+        None => Err(operand_error("add", &left, &right, span)),
+    }
+}
+
+pub fn subtract(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if datetime::is_datetime(&left) || datetime::is_datetime(&right) {
+        return datetime::subtract(left, right, span);
+    }
+    if units::is_quantity(&left) || units::is_quantity(&right) {
+        return units::subtract(left, right, span);
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => Ok(left
+            .checked_sub(right)
+            .map(Object::Int)
+            .unwrap_or_else(|| integer(&BigInt::from(left) - &BigInt::from(right)))),
+        Some(Operands::Big(left, right)) => Ok(integer(&left - &right)),
+        Some(Operands::Rational(left, right)) => Ok(rational(left.subtract(&right))),
+        Some(Operands::Decimal(left, right)) => Ok(decimal(left.subtract(&right))),
+        Some(Operands::Float(left, right)) => Ok(Object::Float(left - right)),
+        Some(Operands::Complex(left, right)) => Ok(Object::Complex(left.subtract(&right))),
+        None => Err(operand_error("subtract", &left, &right, span)),
+    }
+}
+
+pub fn multiply(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if units::is_quantity(&left) || units::is_quantity(&right) {
+        return units::multiply(left, right, span);
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => Ok(left
+            .checked_mul(right)
+            .map(Object::Int)
+            .unwrap_or_else(|| integer(&BigInt::from(left) * &BigInt::from(right)))),
+        Some(Operands::Big(left, right)) => Ok(integer(&left * &right)),
+        Some(Operands::Rational(left, right)) => Ok(rational(left.multiply(&right))),
+        Some(Operands::Decimal(left, right)) => Ok(decimal(left.multiply(&right))),
+        Some(Operands::Float(left, right)) => Ok(Object::Float(left * right)),
+        Some(Operands::Complex(left, right)) => Ok(Object::Complex(left.multiply(&right))),
+        None => Err(operand_error("multiply", &left, &right, span)),
+    }
+}
+
+pub fn divide(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    if units::is_quantity(&left) || units::is_quantity(&right) {
+        return units::divide(left, right, span);
+    }
+    if is_zero(&right) && coerce(&left, &right).is_some() {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            ":(".to_string(),
+        ));
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => match left.checked_rem(right) {
+            Some(0) => Ok(left
+                .checked_div(right)
+                .map(Object::Int)
+                .unwrap_or_else(|| integer(-&BigInt::from(left)))),
+            _ => divide_integers(BigInt::from(left), BigInt::from(right), settings),
+        },
+        Some(Operands::Big(left, right)) => divide_integers(left, right, settings),
+        Some(Operands::Rational(left, right)) => {
+            Ok(rational(left.divide(&right).expect("Divisor is not zero")))
+        }
+        Some(Operands::Decimal(left, right)) => left
+            .divide(&right, settings.precision, settings.rounding)
+            .map(decimal)
+            .ok_or_else(|| digits_error(span)),
+        Some(Operands::Float(left, right)) => Ok(Object::Float(left / right)),
+        Some(Operands::Complex(left, right)) => Ok(Object::Complex(left.divide(&right))),
+        None => Err(operand_error("divide", &left, &right, span)),
+    }
+}
+
+fn divide_integers(left: BigInt, right: BigInt, settings: &Settings) -> Result<Object, QclError> {
+    let (quotient, remainder) = left.div_rem(&right).expect("Divisor is not zero");
+    if remainder.is_zero() {
+        return Ok(integer(quotient));
+    }
+    match settings.division {
+        DivisionMode::Float => Ok(Object::Float(left.to_f64() / right.to_f64())),
+        DivisionMode::Rational => Ok(rational(
+            Rational::new(left, right).expect("Divisor is not zero"),
+        )),
+    }
+}
+
+pub fn floor_divide(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if is_zero(&right) && coerce(&left, &right).is_some() {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            "Cannot floor divide by zero".to_string(),
+        ));
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => {
+            let (quotient, _) = BigInt::from(left)
+                .div_mod_floor(&BigInt::from(right))
+                .expect("Divisor is not zero");
+            Ok(integer(quotient))
+        }
+        Some(Operands::Big(left, right)) => {
+            let (quotient, _) = left.div_mod_floor(&right).expect("Divisor is not zero");
+            Ok(integer(quotient))
+        }
+        Some(Operands::Rational(left, right)) => Ok(integer(
+            left.divide(&right).expect("Divisor is not zero").floor(),
+        )),
+        Some(Operands::Decimal(left, right)) => Ok(integer(
+            left.to_rational()
+                .divide(&right.to_rational())
+                .expect("Divisor is not zero")
+                .floor(),
+        )),
+        Some(Operands::Float(left, right)) => Ok(Object::Float((left / right).floor())),
+        Some(Operands::Complex(..)) | None => {
+            Err(operand_error("floor divide", &left, &right, span))
+        }
+    }
+}
+
+pub fn modulo(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if is_zero(&right) && coerce(&left, &right).is_some() {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            "Cannot take the modulo by zero".to_string(),
+        ));
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right)) => {
+            let (_, remainder) = BigInt::from(left)
+                .div_mod_floor(&BigInt::from(right))
+                .expect("Divisor is not zero");
+            Ok(integer(remainder))
+        }
+        Some(Operands::Big(left, right)) => {
+            let (_, remainder) = left.div_mod_floor(&right).expect("Divisor is not zero");
+            Ok(integer(remainder))
+        }
+        Some(Operands::Rational(left, right)) => {
+            let quotient = left.divide(&right).expect("Divisor is not zero").floor();
+            Ok(rational(left.subtract(
+                &right.multiply(&Rational::from_integer(quotient)),
+            )))
+        }
+        Some(Operands::Decimal(left, right)) => {
+            let quotient = left
+                .to_rational()
+                .divide(&right.to_rational())
+                .expect("Divisor is not zero")
+                .floor();
+            Ok(decimal(left.subtract(
+                &right.multiply(&Decimal::from_integer(quotient)),
+            )))
+        }
+        Some(Operands::Float(left, right)) => {
+            Ok(Object::Float(left - right * (left / right).floor()))
+        }
+        Some(Operands::Complex(..)) | None => {
+            Err(operand_error("take the modulo of", &left, &right, span))
+        }
+    }
+}
+
+pub fn power(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    if units::is_quantity(&left) || units::is_quantity(&right) {
+        return units::power(left, right, span);
+    }
+    match coerce(&left, &right) {
+        Some(Operands::Int(left, right))
+            if right < 0 && settings.division == DivisionMode::Rational =>
+        {
+            power_rational(Rational::from_integer(BigInt::from(left)), right, span)
+        }
+        Some(Operands::Int(left, right)) if right < 0 => {
+            Ok(Object::Float((left as f64).powf(right as f64)))
+        }
+        Some(Operands::Int(left, right)) => {
+            let exponent = u32::try_from(right).map_err(|_| overflow_error(span))?;
+            match left.checked_pow(exponent) {
+                Some(result) => Ok(Object::Int(result)),
+                None => {
+                    let left = BigInt::from(left);
+                    expect_power_size(&left, exponent, span)?;
+                    Ok(integer(left.pow(exponent)))
+                }
+            }
+        }
+        Some(Operands::Big(left, right)) => match right.to_i64() {
+            Some(exponent) if exponent < 0 && settings.division == DivisionMode::Rational => {
+                power_rational(Rational::from_integer(left), exponent, span)
+            }
+            Some(exponent) if exponent < 0 => {
+                Ok(Object::Float(left.to_f64().powf(exponent as f64)))
+            }
+            Some(exponent) => {
+                let exponent = u32::try_from(exponent).map_err(|_| overflow_error(span))?;
+                expect_power_size(&left, exponent, span)?;
+                Ok(integer(left.pow(exponent)))
+            }
+            None => Err(overflow_error(span)),
+        },
+        Some(Operands::Rational(left, right)) => match right.numerator.to_i64() {
+            Some(exponent) if right.is_integer() => power_rational(left, exponent, span),
+            _ => Ok(Object::Float(left.to_f64().powf(right.to_f64()))),
+        },
+        Some(Operands::Decimal(left, right)) => match right.to_rational() {
+            exponent if exponent.is_integer() => match exponent.numerator.to_i64() {
+                Some(exponent) => power_decimal(left, exponent, settings, span),
+                None => Err(overflow_error(span)),
+            },
+            _ => Ok(Object::Float(left.to_f64().powf(right.to_f64()))),
+        },
+        Some(Operands::Float(left, right)) => Ok(Object::Float(left.powf(right))),
+        Some(Operands::Complex(left, _)) if is_integer(&right) => {
+            let exponent = to_float(&right).expect("Exponent is an integer") as i64;
+            Ok(Object::Complex(left.powi(exponent)))
+        }
+        Some(Operands::Complex(left, right)) => Ok(Object::Complex(left.pow(&right))),
+        None => Err(operand_error("exponentiate", &left, &right, span)),
+    }
+}
+
+fn power_rational(base: Rational, exponent: i64, span: &Span) -> Result<Object, QclError> {
+    if base.is_zero() && exponent < 0 {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            "Cannot raise zero to a negative power".to_string(),
+        ));
+    }
+    let magnitude = u32::try_from(exponent.unsigned_abs()).map_err(|_| overflow_error(span))?;
+    expect_power_size(&base.numerator, magnitude, span)?;
+    expect_power_size(&base.denominator, magnitude, span)?;
+    base.pow(exponent)
+        .map(rational)
+        .ok_or_else(|| overflow_error(span))
+}
+
+/// Checks that `base^exponent` has at most `MAX_POWER_BITS` bits, estimating
+/// its size as the exponent times the size of the base.
+fn expect_power_size(base: &BigInt, exponent: u32, span: &Span) -> Result<(), QclError> {
+    if base.abs() <= BigInt::from(1) || base.bits() * u64::from(exponent) <= MAX_POWER_BITS {
+        Ok(())
+    } else {
+        Err(overflow_error(span))
+    }
+}
+
+fn power_decimal(
+    base: Decimal,
+    exponent: i64,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    if base.is_zero() && exponent < 0 {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            "Cannot raise zero to a negative power".to_string(),
+        ));
+    }
+    let magnitude = u32::try_from(exponent.unsigned_abs()).map_err(|_| overflow_error(span))?;
+    let exact = base.to_rational();
+    expect_power_size(&exact.numerator, magnitude, span)?;
+    expect_power_size(&exact.denominator, magnitude, span)?;
+    let result = base.pow(magnitude).ok_or_else(|| digits_error(span))?;
+    if exponent >= 0 {
+        return Ok(decimal(result));
+    }
+    Decimal::from_integer(BigInt::from(1))
+        .divide(&result, settings.precision, settings.rounding)
+        .map(decimal)
+        .ok_or_else(|| digits_error(span))
+}
+
+/// Orders two numbers, or returns None if they are not comparable.
+pub fn compare(left: &Object, right: &Object) -> Option<Ordering> {
+    if let (Object::Str(left), Object::Str(right)) = (left, right) {
+        return Some(left.cmp(right));
+    }
+    if let (Object::Percentage(left), Object::Percentage(right)) = (left, right) {
+        return compare(left, right);
+    }
+    if datetime::is_datetime(left) || datetime::is_datetime(right) {
+        return datetime::compare(left, right);
+    }
+    if units::is_quantity(left) || units::is_quantity(right) {
+        return units::compare(left, right);
+    }
+    match coerce(left, right)? {
+        Operands::Int(left, right) => Some(left.cmp(&right)),
+        Operands::Big(left, right) => Some(left.cmp(&right)),
+        Operands::Rational(left, right) => Some(left.cmp(&right)),
+        Operands::Decimal(left, right) => Some(left.cmp(&right)),
+        Operands::Float(left, right) => left.partial_cmp(&right),
+        Operands::Complex(left, right) => (left == right).then_some(Ordering::Equal),
+    }
+}
+
+pub fn operand_error(operation: &str, left: &Object, right: &Object, span: &Span) -> QclError {
+    QclError::new(
+        QclErrorType::TypeError,
+        span.clone(),
+        format!(
+            "Cannot {} {} and {}",
+            operation,
+            left.type_name(),
+            right.type_name()
+        ),
+    )
+}
+
+fn overflow_error(span: &Span) -> QclError {
+    QclError::new(
+        QclErrorType::OverflowError,
+        span.clone(),
+        "Exponent is too large".to_string(),
+    )
+}
+
+fn digits_error(span: &Span) -> QclError {
+    QclError::new(
+        QclErrorType::OverflowError,
+        span.clone(),
+        "Decimal has too many digits after the decimal point".to_string(),
+    )
+}
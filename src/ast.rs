@@ -1,19 +1,121 @@
+use crate::bigint::BigInt;
+use crate::datetime::{Date, DateTime, Time};
+use crate::decimal::Decimal;
+use crate::format::Format;
 use crate::span::Spanned;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub enum Statement {
     Block(Vec<Spanned<Statement>>),
     Print(Box<Spanned<Expression>>),
+    Let(Pattern, Box<Spanned<Expression>>),
+    Assignment(Pattern, Box<Spanned<Expression>>),
+    Function(String, Vec<String>, Rc<Spanned<Statement>>),
+    Return(Box<Spanned<Expression>>),
+    If(
+        Box<Spanned<Expression>>,
+        Box<Spanned<Statement>>,
+        Option<Box<Spanned<Statement>>>,
+    ),
+    While(Box<Spanned<Expression>>, Box<Spanned<Statement>>),
+    For(String, Box<Spanned<Expression>>, Box<Spanned<Statement>>),
+    Break,
+    Continue,
+    Set(String, String),
+    Rates(String),
     Expression(Box<Spanned<Expression>>),
 }
 
 #[derive(Debug)]
 pub enum Expression {
-    Number(f64),
+    Integer(i64),
+    BigInteger(BigInt),
+    Float(f64),
+    Decimal(Decimal),
+    Imaginary(f64),
+    Date(Date),
+    Time(Time),
+    DateTime(DateTime),
+    Quantity(Box<Spanned<Expression>>, Vec<(String, i32)>),
+    Conversion(Box<Spanned<Expression>>, Vec<(String, i32)>),
+    Percentage(Box<Spanned<Expression>>),
+    PercentOf(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    AsPercentOf(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Boolean(bool),
+    String(String),
+    Interpolation(Vec<StringSegment>),
     Name(String),
     Negation(Box<Spanned<Expression>>),
+    Not(Box<Spanned<Expression>>),
+    And(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Or(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Equal(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    NotEqual(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Less(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    LessEqual(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Greater(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    GreaterEqual(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
     Addition(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
     Subtraction(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
     Multiplication(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
     Division(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    FloorDivision(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Modulo(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Power(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Range(
+        Box<Spanned<Expression>>,
+        Box<Spanned<Expression>>,
+        Option<Box<Spanned<Expression>>>,
+        bool,
+    ),
+    Call(Box<Spanned<Expression>>, Vec<Spanned<Expression>>),
+    List(Vec<Spanned<Expression>>),
+    Tuple(Vec<Spanned<Expression>>),
+    Comprehension(
+        Box<Spanned<Expression>>,
+        String,
+        Box<Spanned<Expression>>,
+        Option<Box<Spanned<Expression>>>,
+    ),
+    Index(Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Map(Vec<(String, Spanned<Expression>)>),
+    Field(Box<Spanned<Expression>>, String),
+    Match(Box<Spanned<Expression>>, Vec<MatchArm>),
+    Sum(String, Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Product(String, Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    Lambda(Vec<String>, Rc<Spanned<Statement>>),
+    If(
+        Box<Spanned<Expression>>,
+        Box<Spanned<Expression>>,
+        Box<Spanned<Expression>>,
+    ),
+}
+
+#[derive(Debug)]
+pub enum StringSegment {
+    Text(String),
+    Value(Spanned<Expression>, Format),
+}
+
+/// The target of a `let`, an assignment or a `match` arm. A tuple or list
+/// pattern may hold one `..name` that collects the values not matched by the
+/// others, and a record pattern matches maps holding at least its keys.
+#[derive(Debug)]
+pub enum Pattern {
+    Wildcard,
+    Name(String),
+    Value(Box<Spanned<Expression>>),
+    Range(Box<Spanned<Expression>>, Box<Spanned<Expression>>, bool),
+    Tuple(Vec<Pattern>),
+    List(Vec<Pattern>),
+    Rest(String),
+    Record(Vec<(String, Pattern)>),
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Spanned<Expression>>,
+    pub body: Spanned<Expression>,
 }
@@ -0,0 +1,414 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// An arbitrary-precision integer stored as a sign and little-endian base 2^32
+/// magnitude without trailing zero limbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: Vec<u32>,
+}
+
+impl BigInt {
+    fn from_parts(negative: bool, mut magnitude: Vec<u32>) -> BigInt {
+        while magnitude.last() == Some(&0) {
+            magnitude.pop();
+        }
+        BigInt {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn parse(string: &str) -> Option<BigInt> {
+        let (negative, digits) = match string.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, string),
+        };
+        if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+            return None;
+        }
+        let mut magnitude = Vec::new();
+        for ch in digits.chars() {
+            let digit = ch.to_digit(10)?;
+            let mut carry = digit as u64;
+            for limb in magnitude.iter_mut() {
+                let value = *limb as u64 * 10 + carry;
+                *limb = value as u32;
+                carry = value >> 32;
+            }
+            if carry > 0 {
+                magnitude.push(carry as u32);
+            }
+        }
+        Some(BigInt::from_parts(negative, magnitude))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.magnitude.len() > 2 {
+            return None;
+        }
+        let mut value: u64 = 0;
+        for (index, limb) in self.magnitude.iter().enumerate() {
+            value |= (*limb as u64) << (32 * index);
+        }
+        if self.negative {
+            if value <= i64::MAX as u64 + 1 {
+                Some((value as i64).wrapping_neg())
+            } else {
+                None
+            }
+        } else {
+            i64::try_from(value).ok()
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0.0, |value, limb| value * 4294967296.0 + *limb as f64);
+        if self.negative {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Counts the bits of the magnitude, which is zero for zero.
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(top) => self.magnitude.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    pub fn is_even(&self) -> bool {
+        self.magnitude.first().is_none_or(|limb| limb % 2 == 0)
+    }
+
+    pub fn abs(&self) -> BigInt {
+        BigInt::from_parts(false, self.magnitude.clone())
+    }
+
+    /// Computes the non-negative greatest common divisor.
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let mut left = self.abs();
+        let mut right = other.abs();
+        while !right.is_zero() {
+            let remainder = left.div_rem(&right).expect("Divisor is not zero").1;
+            left = right;
+            right = remainder;
+        }
+        left
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+            base = &base * &base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Divides truncating towards zero, so the remainder takes the sign of
+    /// the dividend. Returns None when dividing by zero.
+    pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        if divisor.is_zero() {
+            return None;
+        }
+        let (quotient, remainder) = match divisor.magnitude[..] {
+            [small] => {
+                let (quotient, remainder) = divide_small(&self.magnitude, small);
+                (quotient, vec![remainder])
+            }
+            _ => divide(&self.magnitude, &divisor.magnitude),
+        };
+        Some((
+            BigInt::from_parts(self.negative != divisor.negative, quotient),
+            BigInt::from_parts(self.negative, remainder),
+        ))
+    }
+
+    /// Divides rounding towards negative infinity, so the remainder takes the
+    /// sign of the divisor. Returns None when dividing by zero.
+    pub fn div_mod_floor(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+        let (quotient, remainder) = self.div_rem(divisor)?;
+        if !remainder.is_zero() && remainder.negative != divisor.negative {
+            Some((&quotient - &BigInt::from(1), &remainder + divisor))
+        } else {
+            Some((quotient, remainder))
+        }
+    }
+
+    pub fn mod_pow(&self, exponent: &BigInt, modulus: &BigInt) -> Option<BigInt> {
+        if exponent.negative {
+            return None;
+        }
+        let mut result = BigInt::from(1).div_mod_floor(modulus)?.1;
+        let mut base = self.div_mod_floor(modulus)?.1;
+        let mut exponent = exponent.clone();
+        let two = BigInt::from(2);
+        while !exponent.is_zero() {
+            if !exponent.is_even() {
+                result = (&result * &base).div_mod_floor(modulus)?.1;
+            }
+            base = (&base * &base).div_mod_floor(modulus)?.1;
+            exponent = exponent.div_rem(&two)?.0;
+        }
+        Some(result)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> BigInt {
+        let magnitude = value.unsigned_abs();
+        BigInt::from_parts(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitudes(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitudes(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::from_parts(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_magnitudes(&self.magnitude, &other.magnitude),
+            );
+        }
+        match compare_magnitudes(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(
+                other.negative,
+                subtract_magnitudes(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                subtract_magnitudes(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: &BigInt) -> BigInt {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: &BigInt) -> BigInt {
+        let mut magnitude = vec![0u32; self.magnitude.len() + other.magnitude.len()];
+        for (i, left) in self.magnitude.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, right) in other.magnitude.iter().enumerate() {
+                let value = magnitude[i + j] as u64 + *left as u64 * *right as u64 + carry;
+                magnitude[i + j] = value as u32;
+                carry = value >> 32;
+            }
+            magnitude[i + other.magnitude.len()] = carry as u32;
+        }
+        BigInt::from_parts(self.negative != other.negative, magnitude)
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = Vec::new();
+        let mut magnitude = self.magnitude.clone();
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = divide_small(&magnitude, 1_000_000_000);
+            chunks.push(remainder);
+            magnitude = quotient;
+            while magnitude.last() == Some(&0) {
+                magnitude.pop();
+            }
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{}", first)?;
+        }
+        for chunk in chunks {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+fn compare_magnitudes(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for index in 0..left.len().max(right.len()) {
+        let value =
+            *left.get(index).unwrap_or(&0) as u64 + *right.get(index).unwrap_or(&0) as u64 + carry;
+        result.push(value as u32);
+        carry = value >> 32;
+    }
+    result.push(carry as u32);
+    result
+}
+
+/// Subtracts `right` from `left`, which must not be the smaller magnitude.
+fn subtract_magnitudes(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (index, limb) in left.iter().enumerate() {
+        let mut value = *limb as i64 - *right.get(index).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if value < 0 {
+            value += 1 << 32;
+            borrow = 1;
+        }
+        result.push(value as u32);
+    }
+    result
+}
+
+fn divide_small(dividend: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder = 0u64;
+    for (index, limb) in dividend.iter().enumerate().rev() {
+        let value = (remainder << 32) | *limb as u64;
+        quotient[index] = (value / divisor as u64) as u32;
+        remainder = value % divisor as u64;
+    }
+    (quotient, remainder as u32)
+}
+
+/// Long division one bit at a time, shifting the dividend into the remainder.
+fn divide(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = Vec::with_capacity(divisor.len() + 1);
+    for bit in (0..dividend.len() * 32).rev() {
+        let mut carry = (dividend[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let shifted = (*limb << 1) | carry;
+            carry = *limb >> 31;
+            *limb = shifted;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+        if compare_magnitudes(&remainder, divisor) != Ordering::Less {
+            remainder = subtract_magnitudes(&remainder, divisor);
+            while remainder.last() == Some(&0) {
+                remainder.pop();
+            }
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(string: &str) -> BigInt {
+        BigInt::parse(string).unwrap()
+    }
+
+    #[test]
+    fn multiplies_across_limbs() {
+        let product =
+            &big("123456789012345678901234567890") * &big("-987654321098765432109876543210");
+        assert_eq!(
+            product.to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+        assert_eq!((&big("0") * &big("-12345678901234567890")).to_string(), "0");
+    }
+
+    #[test]
+    fn divides_long_numbers() {
+        let (quotient, remainder) =
+            big("121932631137021795226185032733622923332237463801111263526911")
+                .div_rem(&big("987654321098765432109876543210"))
+                .unwrap();
+        assert_eq!(quotient.to_string(), "123456789012345678901234567890");
+        assert_eq!(remainder.to_string(), "11");
+        assert!(big("1").div_rem(&big("0")).is_none());
+    }
+
+    #[test]
+    fn truncates_division_towards_zero() {
+        let (quotient, remainder) = big("-7").div_rem(&big("2")).unwrap();
+        assert_eq!(
+            (quotient.to_string(), remainder.to_string()),
+            ("-3".into(), "-1".into())
+        );
+    }
+
+    #[test]
+    fn floors_division_and_takes_the_sign_of_the_divisor() {
+        let cases = [
+            ("7", "2", "3", "1"),
+            ("-7", "2", "-4", "1"),
+            ("7", "-2", "-4", "-1"),
+            ("-7", "-2", "3", "-1"),
+            ("-100000000000000000000", "3", "-33333333333333333334", "2"),
+        ];
+        for (dividend, divisor, quotient, modulo) in cases {
+            let (actual_quotient, actual_modulo) =
+                big(dividend).div_mod_floor(&big(divisor)).unwrap();
+            assert_eq!(actual_quotient.to_string(), quotient);
+            assert_eq!(actual_modulo.to_string(), modulo);
+        }
+    }
+}
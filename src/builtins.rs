@@ -0,0 +1,465 @@
+use crate::arithmetic;
+use crate::bigint::BigInt;
+use crate::complex::Complex;
+use crate::datetime::Date;
+use crate::decimal::Decimal;
+use crate::list;
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::settings::{Settings, MAX_PRECISION};
+use crate::span::Span;
+use std::cmp::Ordering;
+use std::fmt;
+
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: fn(Vec<Object>, &Settings, &Span) -> Result<Object, QclError>,
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
+
+/// The largest argument to `factorial`, whose result already has tens of
+/// thousands of digits. Larger ones would take minutes to compute.
+const MAX_FACTORIAL: i64 = 20_000;
+
+pub static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "factorial",
+        arity: 1,
+        function: factorial,
+    },
+    Builtin {
+        name: "mod_pow",
+        arity: 3,
+        function: mod_pow,
+    },
+    Builtin {
+        name: "to_float",
+        arity: 1,
+        function: to_float,
+    },
+    Builtin {
+        name: "approx",
+        arity: 1,
+        function: to_float,
+    },
+    Builtin {
+        name: "round",
+        arity: 2,
+        function: round,
+    },
+    Builtin {
+        name: "re",
+        arity: 1,
+        function: re,
+    },
+    Builtin {
+        name: "im",
+        arity: 1,
+        function: im,
+    },
+    Builtin {
+        name: "abs",
+        arity: 1,
+        function: abs,
+    },
+    Builtin {
+        name: "arg",
+        arity: 1,
+        function: arg,
+    },
+    Builtin {
+        name: "conj",
+        arity: 1,
+        function: conj,
+    },
+    Builtin {
+        name: "sqrt",
+        arity: 1,
+        function: sqrt,
+    },
+    Builtin {
+        name: "exp",
+        arity: 1,
+        function: exp,
+    },
+    Builtin {
+        name: "ln",
+        arity: 1,
+        function: ln,
+    },
+    Builtin {
+        name: "today",
+        arity: 0,
+        function: today,
+    },
+    Builtin {
+        name: "weekday",
+        arity: 1,
+        function: weekday,
+    },
+    Builtin {
+        name: "business_days",
+        arity: 2,
+        function: business_days,
+    },
+    Builtin {
+        name: "len",
+        arity: 1,
+        function: len,
+    },
+    Builtin {
+        name: "push",
+        arity: 2,
+        function: push,
+    },
+    Builtin {
+        name: "sum",
+        arity: 1,
+        function: sum,
+    },
+    Builtin {
+        name: "prod",
+        arity: 1,
+        function: prod,
+    },
+    Builtin {
+        name: "min",
+        arity: 1,
+        function: min,
+    },
+    Builtin {
+        name: "max",
+        arity: 1,
+        function: max,
+    },
+];
+
+fn factorial(
+    arguments: Vec<Object>,
+    _settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    let n = expect_integer(&arguments[0], span)?;
+    if n.is_negative() {
+        return Err(QclError::new(
+            QclErrorType::ValueError,
+            span.clone(),
+            "Cannot take the factorial of a negative number".to_string(),
+        ));
+    }
+    let n = n.to_i64().filter(|n| *n <= MAX_FACTORIAL).ok_or_else(|| {
+        QclError::new(
+            QclErrorType::OverflowError,
+            span.clone(),
+            format!("Factorial argument is larger than {}", MAX_FACTORIAL),
+        )
+    })?;
+    let mut result = BigInt::from(1);
+    for factor in 2..=n {
+        result = &result * &BigInt::from(factor);
+    }
+    Ok(arithmetic::integer(result))
+}
+
+fn mod_pow(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    let base = expect_integer(&arguments[0], span)?;
+    let exponent = expect_integer(&arguments[1], span)?;
+    let modulus = expect_integer(&arguments[2], span)?;
+    if modulus.is_zero() {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            "Cannot take the modulo by zero".to_string(),
+        ));
+    }
+    match base.mod_pow(&exponent, &modulus) {
+        Some(result) => Ok(arithmetic::integer(result)),
+        None => Err(QclError::new(
+            QclErrorType::ValueError,
+            span.clone(),
+            "mod_pow expects a non-negative exponent".to_string(),
+        )),
+    }
+}
+
+fn to_float(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match arithmetic::to_float(&arguments[0]) {
+        Some(value) => Ok(Object::Float(value)),
+        None => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot convert {} to a float", arguments[0].type_name()),
+        )),
+    }
+}
+
+fn round(arguments: Vec<Object>, settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    let places = expect_integer(&arguments[1], span)?
+        .to_i64()
+        .and_then(|places| i32::try_from(places).ok())
+        .and_then(|places| u32::try_from(places).ok())
+        .ok_or_else(|| {
+            QclError::new(
+                QclErrorType::ValueError,
+                span.clone(),
+                format!("round expects between 0 and {} places", i32::MAX),
+            )
+        })?;
+    match &arguments[0] {
+        Object::Int(_) | Object::BigInt(_) => Ok(arguments[0].clone()),
+        Object::Decimal(value) => Ok(arithmetic::decimal(value.round(places, settings.rounding))),
+        Object::Rational(_) if places > MAX_PRECISION => Err(QclError::new(
+            QclErrorType::ValueError,
+            span.clone(),
+            format!(
+                "Cannot round a rational to more than {} places",
+                MAX_PRECISION
+            ),
+        )),
+        Object::Rational(value) => {
+            let numerator = Decimal::from_integer(value.numerator.clone());
+            let denominator = Decimal::from_integer(value.denominator.clone());
+            Ok(arithmetic::decimal(
+                numerator
+                    .divide(&denominator, places, settings.rounding)
+                    .expect("Denominator is not zero"),
+            ))
+        }
+        // A float has at most 17 significant digits, so rounding to more
+        // places leaves it unchanged, while scaling by 10^places overflows.
+        Object::Float(_) if places >= 17 => Ok(arguments[0].clone()),
+        Object::Float(value) => {
+            let factor = 10f64.powi(places as i32);
+            let rounded = (value * factor).round() / factor;
+            Ok(Object::Float(if rounded.is_finite() {
+                rounded
+            } else {
+                *value
+            }))
+        }
+        value => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot round {}", value.type_name()),
+        )),
+    }
+}
+
+fn re(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Float(value.re)),
+        value => expect_number(value, span).map(|_| value.clone()),
+    }
+}
+
+fn im(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Float(value.im)),
+        value => expect_number(value, span).map(|_| Object::Int(0)),
+    }
+}
+
+fn abs(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    let value = arguments.into_iter().next().expect("Arity is checked");
+    match value {
+        Object::Complex(value) => Ok(Object::Float(value.abs())),
+        value if arithmetic::compare(&value, &Object::Int(0)) == Some(Ordering::Less) => {
+            arithmetic::negate(value, span)
+        }
+        value => expect_number(&value, span).map(|_| value),
+    }
+}
+
+fn arg(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    Ok(Object::Float(expect_complex(&arguments[0], span)?.arg()))
+}
+
+fn conj(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Complex(value.conj())),
+        value => expect_number(value, span).map(|_| value.clone()),
+    }
+}
+
+fn sqrt(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Complex(value.sqrt())),
+        value => match expect_number(value, span)? {
+            number if number < 0.0 => Ok(Object::Complex(Complex::new(number, 0.0).sqrt())),
+            number => Ok(Object::Float(number.sqrt())),
+        },
+    }
+}
+
+fn exp(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Complex(value.exp())),
+        value => Ok(Object::Float(expect_number(value, span)?.exp())),
+    }
+}
+
+fn ln(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Complex(value) => Ok(Object::Complex(value.ln())),
+        value => match expect_number(value, span)? {
+            number if number < 0.0 => Ok(Object::Complex(Complex::new(number, 0.0).ln())),
+            number => Ok(Object::Float(number.ln())),
+        },
+    }
+}
+
+fn today(_arguments: Vec<Object>, _settings: &Settings, _span: &Span) -> Result<Object, QclError> {
+    Ok(Object::Date(Date::today()))
+}
+
+/// Returns the ISO weekday, from 1 for Monday to 7 for Sunday.
+fn weekday(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    Ok(Object::Int(expect_date(&arguments[0], span)?.weekday()))
+}
+
+/// Counts the weekdays from the first date up to but excluding the second.
+fn business_days(
+    arguments: Vec<Object>,
+    _settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    let start = expect_date(&arguments[0], span)?;
+    let end = expect_date(&arguments[1], span)?;
+    Ok(Object::Int(start.business_days_until(&end)))
+}
+
+fn len(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::Str(string) => Ok(Object::Int(string.chars().count() as i64)),
+        Object::List(values) => Ok(Object::Int(values.borrow().len() as i64)),
+        Object::Map(entries) => Ok(Object::Int(entries.len() as i64)),
+        Object::Tuple(values) => Ok(Object::Int(values.len() as i64)),
+        value => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot take the length of {}", value.type_name()),
+        )),
+    }
+}
+
+/// Appends a value to the end of a list in place.
+fn push(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match &arguments[0] {
+        Object::List(values) => {
+            values.borrow_mut().push(arguments[1].clone());
+            Ok(Object::Nil)
+        }
+        values => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot push onto {}", values.type_name()),
+        )),
+    }
+}
+
+fn sum(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    let total = fold(&arguments[0], span, |left, right| {
+        arithmetic::add(left, right, span)
+    })?;
+    Ok(total.unwrap_or(Object::Int(0)))
+}
+
+fn prod(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    let product = fold(&arguments[0], span, |left, right| {
+        arithmetic::multiply(left, right, span)
+    })?;
+    Ok(product.unwrap_or(Object::Int(1)))
+}
+
+fn min(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    extreme(&arguments[0], "min", Ordering::Less, span)
+}
+
+fn max(arguments: Vec<Object>, _settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    extreme(&arguments[0], "max", Ordering::Greater, span)
+}
+
+/// Finds the element that orders as `wanted` against every other one.
+fn extreme(values: &Object, name: &str, wanted: Ordering, span: &Span) -> Result<Object, QclError> {
+    let result = fold(values, span, |best, value| {
+        match arithmetic::compare(&value, &best) {
+            Some(ordering) if ordering == wanted => Ok(value),
+            Some(_) => Ok(best),
+            None => Err(arithmetic::operand_error("compare", &value, &best, span)),
+        }
+    })?;
+    result.ok_or_else(|| {
+        QclError::new(
+            QclErrorType::ValueError,
+            span.clone(),
+            format!("Cannot take the {} of an empty sequence", name),
+        )
+    })
+}
+
+/// Combines the elements of a range, list or tuple from left to right, or
+/// returns None if there are none.
+fn fold(
+    values: &Object,
+    span: &Span,
+    combine: impl Fn(Object, Object) -> Result<Object, QclError>,
+) -> Result<Option<Object>, QclError> {
+    let mut result = None;
+    for index in 0.. {
+        let Some(value) = list::element(values, index, span)? else {
+            break;
+        };
+        result = Some(match result {
+            Some(result) => combine(result, value)?,
+            None => value,
+        });
+    }
+    Ok(result)
+}
+
+fn expect_date(value: &Object, span: &Span) -> Result<Date, QclError> {
+    match value {
+        Object::Date(date) => Ok(*date),
+        Object::DateTime(datetime) => Ok(datetime.date()),
+        value => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Expected a date but found {}", value.type_name()),
+        )),
+    }
+}
+
+fn expect_number(value: &Object, span: &Span) -> Result<f64, QclError> {
+    arithmetic::to_float(value).ok_or_else(|| {
+        QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Expected a number but found {}", value.type_name()),
+        )
+    })
+}
+
+fn expect_complex(value: &Object, span: &Span) -> Result<Complex, QclError> {
+    match value {
+        Object::Complex(value) => Ok(*value),
+        value => Ok(Complex::new(expect_number(value, span)?, 0.0)),
+    }
+}
+
+fn expect_integer(value: &Object, span: &Span) -> Result<BigInt, QclError> {
+    match value {
+        Object::Int(value) => Ok(BigInt::from(*value)),
+        Object::BigInt(value) => Ok((**value).clone()),
+        value => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Expected an int but found {}", value.type_name()),
+        )),
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex::new(self.re, -self.im)
+    }
+
+    pub fn negate(&self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+
+    pub fn add(&self, other: &Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+
+    pub fn subtract(&self, other: &Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+
+    pub fn multiply(&self, other: &Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+
+    pub fn divide(&self, other: &Complex) -> Complex {
+        let denominator = other.re * other.re + other.im * other.im;
+        Complex::new(
+            (self.re * other.re + self.im * other.im) / denominator,
+            (self.im * other.re - self.re * other.im) / denominator,
+        )
+    }
+
+    pub fn exp(&self) -> Complex {
+        let magnitude = self.re.exp();
+        Complex::new(magnitude * self.im.cos(), magnitude * self.im.sin())
+    }
+
+    /// The principal natural logarithm.
+    pub fn ln(&self) -> Complex {
+        Complex::new(self.abs().ln(), self.arg())
+    }
+
+    /// The principal square root, which has a non-negative real part.
+    pub fn sqrt(&self) -> Complex {
+        let magnitude = self.abs();
+        let re = ((magnitude + self.re) / 2.0).sqrt();
+        let im = ((magnitude - self.re) / 2.0).sqrt();
+        Complex::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    /// Raises to an integer power by repeated squaring, which keeps results
+    /// like `i^2` exact.
+    pub fn powi(&self, exponent: i64) -> Complex {
+        let mut result = Complex::new(1.0, 0.0);
+        let mut base = *self;
+        let mut remaining = exponent.unsigned_abs();
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = result.multiply(&base);
+            }
+            base = base.multiply(&base);
+            remaining >>= 1;
+        }
+        if exponent < 0 {
+            Complex::new(1.0, 0.0).divide(&result)
+        } else {
+            result
+        }
+    }
+
+    pub fn pow(&self, exponent: &Complex) -> Complex {
+        if self.is_zero() {
+            return if exponent.is_zero() {
+                Complex::new(1.0, 0.0)
+            } else {
+                Complex::new(0.0, 0.0)
+            };
+        }
+        exponent.multiply(&self.ln()).exp()
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let imaginary = match self.im {
+            1.0 => "i".to_string(),
+            -1.0 => "-i".to_string(),
+            im => format!("{}i", im),
+        };
+        if self.im == 0.0 {
+            write!(f, "{}", self.re)
+        } else if self.re == 0.0 {
+            write!(f, "{}", imaginary)
+        } else if imaginary.starts_with('-') {
+            write!(f, "{}{}", self.re, imaginary)
+        } else {
+            write!(f, "{}+{}", self.re, imaginary)
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use crate::arithmetic;
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::span::Span;
+use crate::units;
+use std::cmp::Ordering;
+use std::fmt;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// A calendar date, stored as the number of days since 1970-01-01.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: i64,
+}
+
+/// A time of day, stored as the number of seconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Time {
+    seconds: i64,
+}
+
+/// A date and time of day, stored as the number of seconds since
+/// 1970-01-01 00:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DateTime {
+    seconds: i64,
+}
+
+impl Date {
+    /// Builds a date from a year, month and day, or returns None if the day
+    /// does not exist.
+    pub fn from_civil(year: i64, month: i64, day: i64) -> Option<Date> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+        // Counts from 0000-03-01 so that leap days fall at the end of a year.
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Date {
+            days: era * 146097 + day_of_era - 719468,
+        })
+    }
+
+    /// Parses `YYYY-MM-DD`.
+    pub fn parse(string: &str) -> Option<Date> {
+        let mut parts = string.split('-').map(|part| part.parse().ok());
+        let date = Date::from_civil(parts.next()??, parts.next()??, parts.next()??);
+        if parts.next().is_some() {
+            return None;
+        }
+        date
+    }
+
+    pub fn today() -> Date {
+        let seconds = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(0);
+        Date {
+            days: seconds.div_euclid(SECONDS_PER_DAY),
+        }
+    }
+
+    pub fn civil(&self) -> (i64, i64, i64) {
+        let days = self.days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 {
+            month_index + 3
+        } else {
+            month_index - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    /// Returns the ISO weekday, from 1 for Monday to 7 for Sunday.
+    pub fn weekday(&self) -> i64 {
+        (self.days + 3).rem_euclid(7) + 1
+    }
+
+    /// Counts the Mondays to Fridays from this date up to but excluding `end`,
+    /// negated when `end` comes first.
+    pub fn business_days_until(&self, end: &Date) -> i64 {
+        if end < self {
+            return -end.business_days_until(self);
+        }
+        let total = end.days - self.days;
+        let mut count = total / 7 * 5;
+        for offset in 0..total % 7 {
+            let date = Date {
+                days: self.days + total / 7 * 7 + offset,
+            };
+            if date.weekday() <= 5 {
+                count += 1;
+            }
+        }
+        count
+    }
+
+    fn at(&self, time: &Time) -> DateTime {
+        DateTime {
+            seconds: self.days * SECONDS_PER_DAY + time.seconds,
+        }
+    }
+}
+
+impl Time {
+    /// Parses `HH:MM` or `HH:MM:SS`.
+    pub fn parse(string: &str) -> Option<Time> {
+        let parts: Vec<i64> = string
+            .split(':')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        let (hours, minutes, seconds) = match parts[..] {
+            [hours, minutes] => (hours, minutes, 0),
+            [hours, minutes, seconds] => (hours, minutes, seconds),
+            _ => return None,
+        };
+        if hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+        Some(Time {
+            seconds: hours * 3600 + minutes * 60 + seconds,
+        })
+    }
+}
+
+impl DateTime {
+    pub fn new(date: Date, time: Time) -> DateTime {
+        date.at(&time)
+    }
+
+    pub fn date(&self) -> Date {
+        Date {
+            days: self.seconds.div_euclid(SECONDS_PER_DAY),
+        }
+    }
+
+    fn time(&self) -> Time {
+        Time {
+            seconds: self.seconds.rem_euclid(SECONDS_PER_DAY),
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.civil();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (hours, minutes, seconds) = (
+            self.seconds / 3600,
+            self.seconds / 60 % 60,
+            self.seconds % 60,
+        );
+        if seconds == 0 {
+            write!(f, "{:02}:{:02}", hours, minutes)
+        } else {
+            write!(f, "{:02}:{:02}:{:02}", hours, minutes, seconds)
+        }
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.date(), self.time())
+    }
+}
+
+pub fn is_datetime(value: &Object) -> bool {
+    matches!(
+        value,
+        Object::Date(_) | Object::Time(_) | Object::DateTime(_)
+    )
+}
+
+/// Builds the duration between two points in time, in the largest unit that
+/// divides it evenly.
+fn duration(seconds: i64) -> Object {
+    let (size, unit) = [(SECONDS_PER_DAY, "days"), (3600, "h"), (60, "min")]
+        .into_iter()
+        .find(|(size, _)| seconds % size == 0)
+        .unwrap_or((1, "s"));
+    units::duration((seconds / size) as f64, unit)
+}
+
+/// Moves a point in time by a duration, or returns None when the result is
+/// out of range. A date moved by a whole number of days stays a date, and
+/// otherwise gains a time of day.
+fn shift(value: &Object, seconds: f64) -> Option<Object> {
+    let seconds = seconds.round();
+    if !(i64::MIN as f64..i64::MAX as f64).contains(&seconds) {
+        return None;
+    }
+    let seconds = seconds as i64;
+    match value {
+        Object::Date(date) if seconds % SECONDS_PER_DAY == 0 => {
+            let days = date.days.checked_add(seconds / SECONDS_PER_DAY)?;
+            // Every date needs a timestamp to be compared and subtracted.
+            days.checked_mul(SECONDS_PER_DAY)?;
+            Some(Object::Date(Date { days }))
+        }
+        Object::Date(date) => Some(Object::DateTime(DateTime {
+            seconds: date
+                .days
+                .checked_mul(SECONDS_PER_DAY)?
+                .checked_add(seconds)?,
+        })),
+        Object::DateTime(datetime) => Some(Object::DateTime(DateTime {
+            seconds: datetime.seconds.checked_add(seconds)?,
+        })),
+        Object::Time(time) => Some(Object::Time(Time {
+            seconds: (time.seconds + seconds.rem_euclid(SECONDS_PER_DAY)) % SECONDS_PER_DAY,
+        })),
+        _ => None,
+    }
+}
+
+pub fn add(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    match (&left, &right) {
+        (Object::Date(date), Object::Time(time)) | (Object::Time(time), Object::Date(date)) => {
+            Ok(Object::DateTime(date.at(time)))
+        }
+        (value, duration) | (duration, value)
+            if is_datetime(value) && units::seconds(duration).is_some() =>
+        {
+            let seconds = units::seconds(duration).expect("Duration has seconds");
+            shift(value, seconds).ok_or_else(|| range_error(span))
+        }
+        _ => Err(arithmetic::operand_error("add", &left, &right, span)),
+    }
+}
+
+pub fn subtract(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    match (&left, &right) {
+        (Object::Time(left), Object::Time(right)) => Ok(duration(left.seconds - right.seconds)),
+        (value, duration) if is_datetime(value) && units::seconds(duration).is_some() => {
+            let seconds = units::seconds(duration).expect("Duration has seconds");
+            shift(value, -seconds).ok_or_else(|| range_error(span))
+        }
+        _ => match timestamp(&left).zip(timestamp(&right)) {
+            Some((left, right)) => left
+                .checked_sub(right)
+                .map(duration)
+                .ok_or_else(|| range_error(span)),
+            None => Err(arithmetic::operand_error("subtract", &left, &right, span)),
+        },
+    }
+}
+
+fn range_error(span: &Span) -> QclError {
+    QclError::new(
+        QclErrorType::OverflowError,
+        span.clone(),
+        "Date is out of range".to_string(),
+    )
+}
+
+/// Returns the seconds since 1970-01-01 of a date or a date and time.
+fn timestamp(value: &Object) -> Option<i64> {
+    match value {
+        Object::Date(date) => Some(date.days * SECONDS_PER_DAY),
+        Object::DateTime(datetime) => Some(datetime.seconds),
+        _ => None,
+    }
+}
+
+pub fn compare(left: &Object, right: &Object) -> Option<Ordering> {
+    match (left, right) {
+        (Object::Time(left), Object::Time(right)) => Some(left.cmp(right)),
+        (left, right) => Some(timestamp(left)?.cmp(&timestamp(right)?)),
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(string: &str) -> Date {
+        Date::parse(string).unwrap()
+    }
+
+    #[test]
+    fn converts_civil_dates() {
+        for string in [
+            "1970-01-01",
+            "2000-02-29",
+            "1969-12-31",
+            "2024-03-01",
+            "1600-01-01",
+        ] {
+            assert_eq!(date(string).to_string(), string);
+        }
+        assert_eq!(date("1970-01-01").days, 0);
+        assert_eq!(date("2000-03-01").days - date("2000-02-28").days, 2);
+        assert!(Date::parse("2023-02-29").is_none());
+        assert!(Date::parse("1900-02-29").is_none());
+        assert!(Date::parse("2024-13-01").is_none());
+    }
+
+    #[test]
+    fn shifts_dates_by_durations() {
+        let shifted = shift(&Object::Date(date("2024-02-28")), 2.0 * 86400.0).unwrap();
+        assert_eq!(shifted, Object::Date(date("2024-03-01")));
+        let shifted = shift(&Object::Date(date("2024-01-01")), -3600.0).unwrap();
+        assert_eq!(shifted.to_string(), "2023-12-31 23:00");
+        let time = Time::parse("23:30").unwrap();
+        let shifted = shift(&Object::Time(time), 3600.0).unwrap();
+        assert_eq!(shifted.to_string(), "00:30");
+    }
+
+    #[test]
+    fn refuses_shifts_out_of_range() {
+        let date = Object::Date(date("2026-01-01"));
+        assert!(shift(&date, 1e17 * 86400.0).is_none());
+        assert!(shift(&date, 1e30).is_none());
+        assert!(shift(&date, f64::NAN).is_none());
+        let far = shift(&date, 1e13 * 86400.0 + 1.0).unwrap();
+        assert!(shift(&far, 9e18).is_none());
+        let span = Span::new(std::rc::Rc::new(String::new()), 0, 0);
+        assert!(subtract(far, Object::Date(Date { days: -1e14 as i64 }), &span).is_err());
+    }
+
+    #[test]
+    fn finds_iso_weekdays() {
+        assert_eq!(date("1970-01-01").weekday(), 4);
+        assert_eq!(date("2024-01-01").weekday(), 1);
+        assert_eq!(date("2024-01-07").weekday(), 7);
+    }
+
+    #[test]
+    fn counts_business_days() {
+        let monday = date("2024-01-01");
+        assert_eq!(monday.business_days_until(&date("2024-01-01")), 0);
+        assert_eq!(monday.business_days_until(&date("2024-01-06")), 5);
+        assert_eq!(monday.business_days_until(&date("2024-01-08")), 5);
+        assert_eq!(monday.business_days_until(&date("2024-01-10")), 7);
+        assert_eq!(
+            date("2024-01-06").business_days_until(&date("2024-01-08")),
+            0
+        );
+        assert_eq!(date("2024-01-10").business_days_until(&monday), -7);
+    }
+}
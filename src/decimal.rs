@@ -0,0 +1,253 @@
+use crate::bigint::BigInt;
+use crate::rational::Rational;
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingMode {
+    HalfEven,
+    HalfUp,
+    Truncate,
+}
+
+/// An exact base-10 number, `coefficient * 10^-scale`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    coefficient: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    pub fn parse(string: &str) -> Option<Decimal> {
+        let (whole, fraction) = string.split_once('.').unwrap_or((string, ""));
+        Some(Decimal {
+            coefficient: BigInt::parse(&format!("{}{}", whole, fraction))?,
+            scale: fraction.len() as u32,
+        })
+    }
+
+    pub fn from_integer(value: BigInt) -> Decimal {
+        Decimal {
+            coefficient: value,
+            scale: 0,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.coefficient.is_zero()
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        Rational::new(self.coefficient.clone(), power_of_ten(self.scale))
+            .expect("Powers of ten are not zero")
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Rewrites `self` with a larger scale without changing its value.
+    fn rescale(&self, scale: u32) -> BigInt {
+        &self.coefficient * &power_of_ten(scale - self.scale)
+    }
+
+    pub fn negate(&self) -> Decimal {
+        Decimal {
+            coefficient: -&self.coefficient,
+            scale: self.scale,
+        }
+    }
+
+    pub fn add(&self, other: &Decimal) -> Decimal {
+        let scale = self.scale.max(other.scale);
+        Decimal {
+            coefficient: &self.rescale(scale) + &other.rescale(scale),
+            scale,
+        }
+    }
+
+    pub fn subtract(&self, other: &Decimal) -> Decimal {
+        self.add(&other.negate())
+    }
+
+    pub fn multiply(&self, other: &Decimal) -> Decimal {
+        Decimal {
+            coefficient: &self.coefficient * &other.coefficient,
+            scale: self.scale + other.scale,
+        }
+    }
+
+    /// Divides to `precision` digits after the decimal point, or returns None
+    /// when dividing by zero or when the digits needed do not fit in a u32.
+    pub fn divide(&self, other: &Decimal, precision: u32, mode: RoundingMode) -> Option<Decimal> {
+        let numerator = &self.coefficient * &power_of_ten(precision.checked_add(other.scale)?);
+        let denominator = &other.coefficient * &power_of_ten(self.scale);
+        let coefficient = divide_rounded(&numerator, &denominator, mode)?;
+        Some(
+            Decimal {
+                coefficient,
+                scale: precision,
+            }
+            .normalized(),
+        )
+    }
+
+    pub fn round(&self, places: u32, mode: RoundingMode) -> Decimal {
+        if self.scale <= places {
+            return self.clone();
+        }
+        let divisor = power_of_ten(self.scale - places);
+        Decimal {
+            coefficient: divide_rounded(&self.coefficient, &divisor, mode)
+                .expect("Powers of ten are not zero"),
+            scale: places,
+        }
+    }
+
+    /// Formats with exactly `places` digits after the decimal point.
+    pub fn to_fixed(&self, places: u32, mode: RoundingMode) -> String {
+        let rounded = self.round(places, mode);
+        Decimal {
+            coefficient: rounded.rescale(places),
+            scale: places,
+        }
+        .to_string()
+    }
+
+    /// Raises to a power, or returns None when the digits after the decimal
+    /// point do not fit in a u32.
+    pub fn pow(&self, exponent: u32) -> Option<Decimal> {
+        Some(Decimal {
+            coefficient: self.coefficient.pow(exponent),
+            scale: self.scale.checked_mul(exponent)?,
+        })
+    }
+
+    /// Strips trailing zeros after the decimal point.
+    fn normalized(mut self) -> Decimal {
+        let ten = BigInt::from(10);
+        while self.scale > 0 {
+            let (quotient, remainder) = self.coefficient.div_rem(&ten).expect("Ten is not zero");
+            if !remainder.is_zero() {
+                break;
+            }
+            self.coefficient = quotient;
+            self.scale -= 1;
+        }
+        self
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.rescale(scale).cmp(&other.rescale(scale))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.coefficient.to_string();
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", digits.as_str()),
+        };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, whole, fraction)
+    }
+}
+
+fn power_of_ten(exponent: u32) -> BigInt {
+    BigInt::from(10).pow(exponent)
+}
+
+/// Divides two integers, rounding the quotient according to `mode`.
+fn divide_rounded(numerator: &BigInt, denominator: &BigInt, mode: RoundingMode) -> Option<BigInt> {
+    let (quotient, remainder) = numerator.div_rem(denominator)?;
+    if remainder.is_zero() || mode == RoundingMode::Truncate {
+        return Some(quotient);
+    }
+    let twice_remainder = &remainder.abs() * &BigInt::from(2);
+    let round_away = match twice_remainder.cmp(&denominator.abs()) {
+        Ordering::Less => false,
+        Ordering::Greater => true,
+        Ordering::Equal => mode == RoundingMode::HalfUp || !quotient.is_even(),
+    };
+    if !round_away {
+        Some(quotient)
+    } else if numerator.is_negative() != denominator.is_negative() {
+        Some(&quotient - &BigInt::from(1))
+    } else {
+        Some(&quotient + &BigInt::from(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(string: &str, places: u32, mode: RoundingMode) -> String {
+        Decimal::parse(string)
+            .unwrap()
+            .round(places, mode)
+            .to_string()
+    }
+
+    #[test]
+    fn rounds_halves_to_even() {
+        assert_eq!(round("2.5", 0, RoundingMode::HalfEven), "2");
+        assert_eq!(round("3.5", 0, RoundingMode::HalfEven), "4");
+        assert_eq!(round("-2.5", 0, RoundingMode::HalfEven), "-2");
+        assert_eq!(round("0.125", 2, RoundingMode::HalfEven), "0.12");
+        assert_eq!(round("0.1251", 2, RoundingMode::HalfEven), "0.13");
+    }
+
+    #[test]
+    fn rounds_halves_up() {
+        assert_eq!(round("2.5", 0, RoundingMode::HalfUp), "3");
+        assert_eq!(round("-2.5", 0, RoundingMode::HalfUp), "-3");
+        assert_eq!(round("0.125", 2, RoundingMode::HalfUp), "0.13");
+        assert_eq!(round("0.124", 2, RoundingMode::HalfUp), "0.12");
+    }
+
+    #[test]
+    fn pads_fixed_places() {
+        let value = Decimal::parse("1.005").unwrap();
+        assert_eq!(value.to_fixed(2, RoundingMode::HalfEven), "1.00");
+        assert_eq!(value.to_fixed(2, RoundingMode::HalfUp), "1.01");
+        assert_eq!(value.to_fixed(5, RoundingMode::HalfUp), "1.00500");
+    }
+
+    #[test]
+    fn rounds_quotients() {
+        let two = Decimal::parse("2").unwrap();
+        let three = Decimal::parse("3").unwrap();
+        let quotient = two.divide(&three, 3, RoundingMode::HalfEven).unwrap();
+        assert_eq!(quotient.to_string(), "0.667");
+        assert!(two
+            .divide(&Decimal::parse("0").unwrap(), 3, RoundingMode::HalfEven)
+            .is_none());
+    }
+
+    #[test]
+    fn refuses_scales_that_overflow() {
+        let tenth = Decimal::parse("0.1").unwrap();
+        assert!(Decimal::parse("0.01").unwrap().pow(u32::MAX).is_none());
+        assert_eq!(tenth.pow(3).unwrap().to_string(), "0.001");
+        let one = Decimal::parse("1").unwrap();
+        assert!(one
+            .divide(&tenth, u32::MAX, RoundingMode::HalfEven)
+            .is_none());
+    }
+}
@@ -1,14 +1,52 @@
-use crate::ast::Expression;
+use crate::object::Object;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 pub struct Environment {
-    values: HashMap<String, Expression>,
+    values: HashMap<String, Object>,
+    parent: Option<Rc<RefCell<Environment>>>,
 }
 
 impl Environment {
     pub fn new() -> Environment {
         Environment {
             values: HashMap::new(),
+            parent: None,
+        }
+    }
+
+    pub fn with_parent(parent: Rc<RefCell<Environment>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            parent: Some(parent),
+        }
+    }
+
+    /// Binds `name` in this scope, shadowing any binding of an enclosing scope.
+    pub fn define(&mut self, name: String, value: Object) {
+        self.values.insert(name, value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self.parent.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Rebinds the innermost existing binding of `name`, which may live in an
+    /// enclosing scope. Returns false if `name` is not bound anywhere.
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        match self.values.get_mut(name) {
+            Some(slot) => {
+                *slot = value;
+                true
+            }
+            None => match &self.parent {
+                Some(parent) => parent.borrow_mut().assign(name, value),
+                None => false,
+            },
         }
     }
 }
@@ -0,0 +1,91 @@
+use crate::arithmetic;
+use crate::bigint::BigInt;
+use crate::decimal::{Decimal, RoundingMode};
+use crate::object::Object;
+
+/// How a value interpolated into a string is written, following the
+/// `{value:>8.2}` syntax: an optional alignment, a minimum width and a number
+/// of digits after the decimal point.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Format {
+    align: Option<char>,
+    width: usize,
+    precision: Option<u32>,
+}
+
+impl Format {
+    /// Parses the specifier after the colon, or returns None if it is not one.
+    pub fn parse(spec: &str) -> Option<Format> {
+        let (align, rest) = match spec.chars().next() {
+            Some(align @ ('<' | '>' | '^')) => (Some(align), &spec[1..]),
+            _ => (None, spec),
+        };
+        let (width, precision) = match rest.split_once('.') {
+            Some((width, precision)) => (width, Some(precision)),
+            None => (rest, None),
+        };
+        let digits =
+            |string: &str| !string.is_empty() && string.chars().all(|ch| ch.is_ascii_digit());
+        if spec.is_empty() || !(width.is_empty() || digits(width)) {
+            return None;
+        }
+        Some(Format {
+            align,
+            width: width.parse().unwrap_or(0),
+            precision: match precision {
+                Some(precision) if digits(precision) => Some(precision.parse().ok()?),
+                Some(_) => return None,
+                None => None,
+            },
+        })
+    }
+
+    pub fn apply(&self, value: &Object, mode: RoundingMode) -> String {
+        let text = match self.precision {
+            Some(places) => fixed(value, places, mode),
+            None => value.to_string(),
+        };
+        let padding = self.width.saturating_sub(text.chars().count());
+        let align = self.align.unwrap_or(match value {
+            Object::Quantity(_) | Object::Percentage(_) => '>',
+            value if arithmetic::is_number(value) => '>',
+            _ => '<',
+        });
+        match align {
+            '>' => format!("{}{}", " ".repeat(padding), text),
+            '^' => format!(
+                "{}{}{}",
+                " ".repeat(padding / 2),
+                text,
+                " ".repeat(padding - padding / 2)
+            ),
+            _ => format!("{}{}", text, " ".repeat(padding)),
+        }
+    }
+}
+
+/// Writes a number with exactly `places` digits after the decimal point,
+/// rounding exact numbers with `mode`.
+fn fixed(value: &Object, places: u32, mode: RoundingMode) -> String {
+    match value {
+        Object::Int(number) => Decimal::from_integer(BigInt::from(*number)).to_fixed(places, mode),
+        Object::BigInt(number) => Decimal::from_integer((**number).clone()).to_fixed(places, mode),
+        Object::Rational(number) => Decimal::from_integer(number.numerator.clone())
+            .divide(
+                &Decimal::from_integer(number.denominator.clone()),
+                places,
+                mode,
+            )
+            .expect("Denominator is not zero")
+            .to_fixed(places, mode),
+        Object::Decimal(number) => number.to_fixed(places, mode),
+        Object::Float(number) => format!("{:.*}", places as usize, number),
+        Object::Quantity(quantity) => format!(
+            "{} {}",
+            fixed(&Object::Float(quantity.value), places, mode),
+            quantity.unit
+        ),
+        Object::Percentage(percent) => format!("{}%", fixed(percent, places, mode)),
+        value => value.to_string(),
+    }
+}
@@ -1,84 +1,1022 @@
-use crate::ast::{Expression, Statement};
-use crate::object::Object;
+use crate::arithmetic;
+use crate::ast::{Expression, Pattern, Statement, StringSegment};
+use crate::builtins::BUILTINS;
+use crate::complex::Complex;
+use crate::environment::Environment;
+use crate::list;
+use crate::map;
+use crate::object::{Function, Object, Range};
+use crate::percent;
 use crate::qcl_error::{QclError, QclErrorType};
-use crate::span::Spanned;
+use crate::rates::Rates;
+use crate::settings::Settings;
+use crate::span::{Span, Spanned};
+use crate::units::{self, Unit};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
 
 pub struct Interpreter {
-    ast: Spanned<Statement>,
+    ast: Rc<Spanned<Statement>>,
+    environment: Rc<RefCell<Environment>>,
+    settings: Settings,
+    rates: Rates,
+}
+
+enum ControlFlow {
+    Next,
+    Break,
+    Continue,
+    Return(Object),
 }
 
 impl Interpreter {
-    pub fn new(ast: Spanned<Statement>) -> Self {
-        Interpreter { ast }
+    pub fn new(ast: Spanned<Statement>, rates: Rates) -> Self {
+        let mut environment = Environment::new();
+        for builtin in BUILTINS {
+            environment.define(builtin.name.to_string(), Object::Builtin(builtin));
+        }
+        Interpreter {
+            ast: Rc::new(ast),
+            environment: Rc::new(RefCell::new(environment)),
+            settings: Settings::new(),
+            rates,
+        }
     }
 
-    pub fn interpret(&self) -> Result<(), QclError> {
-        self.interpret_statement(&self.ast)
+    pub fn interpret(&mut self) -> Result<(), QclError> {
+        let ast = self.ast.clone();
+        self.interpret_statement(&ast)?;
+        Ok(())
     }
 
-    fn interpret_statement(&self, statement: &Spanned<Statement>) -> Result<(), QclError> {
+    fn interpret_statement(
+        &mut self,
+        statement: &Spanned<Statement>,
+    ) -> Result<ControlFlow, QclError> {
         match &statement.item {
             Statement::Print(expression) => {
                 let evaluated = self.interpret_expresssion(expression)?;
-                println!("{:?}", evaluated);
-                Ok(())
+                println!("{}", evaluated);
+                Ok(ControlFlow::Next)
+            }
+            Statement::Let(pattern, expression) => {
+                let value = self.interpret_expresssion(expression)?;
+                self.bind(pattern, value, true, &statement.span)?;
+                Ok(ControlFlow::Next)
+            }
+            Statement::Assignment(pattern, expression) => {
+                let value = self.interpret_expresssion(expression)?;
+                self.bind(pattern, value, false, &statement.span)?;
+                Ok(ControlFlow::Next)
+            }
+            Statement::Function(name, parameters, body) => {
+                let function = Function {
+                    name: name.clone(),
+                    parameters: parameters.clone(),
+                    body: body.clone(),
+                    environment: self.environment.clone(),
+                };
+                self.environment
+                    .borrow_mut()
+                    .define(name.clone(), Object::Function(Rc::new(function)));
+                Ok(ControlFlow::Next)
+            }
+            Statement::Return(expression) => {
+                let value = self.interpret_expresssion(expression)?;
+                Ok(ControlFlow::Return(value))
+            }
+            Statement::If(condition, then_branch, else_branch) => {
+                if self.interpret_condition(condition)? {
+                    self.interpret_statement(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.interpret_statement(else_branch)
+                } else {
+                    Ok(ControlFlow::Next)
+                }
+            }
+            Statement::While(condition, body) => {
+                while self.interpret_condition(condition)? {
+                    let environment = Environment::with_parent(self.environment.clone());
+                    match self.interpret_body(body, environment)? {
+                        ControlFlow::Break => break,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                        ControlFlow::Next | ControlFlow::Continue => (),
+                    }
+                }
+                Ok(ControlFlow::Next)
             }
+            Statement::For(name, iterable, body) => {
+                let values = self.interpret_expresssion(iterable)?;
+                for index in 0.. {
+                    let value = match list::element(&values, index, &iterable.span)? {
+                        Some(value) => value,
+                        None => break,
+                    };
+                    let mut environment = Environment::with_parent(self.environment.clone());
+                    environment.define(name.clone(), value);
+                    match self.interpret_body(body, environment)? {
+                        ControlFlow::Break => break,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                        ControlFlow::Next | ControlFlow::Continue => (),
+                    }
+                }
+                Ok(ControlFlow::Next)
+            }
+            Statement::Set(name, value) => match self.settings.set(name, value) {
+                Ok(()) => Ok(ControlFlow::Next),
+                Err(message) => Err(QclError::new(
+                    QclErrorType::ValueError,
+                    statement.span.clone(),
+                    message,
+                )),
+            },
+            Statement::Rates(path) => match Rates::load(path) {
+                Ok(rates) => {
+                    self.rates = rates;
+                    Ok(ControlFlow::Next)
+                }
+                Err(message) => Err(QclError::new(
+                    QclErrorType::ValueError,
+                    statement.span.clone(),
+                    message,
+                )),
+            },
+            Statement::Break => Ok(ControlFlow::Break),
+            Statement::Continue => Ok(ControlFlow::Continue),
             Statement::Expression(expression) => {
                 self.interpret_expresssion(expression)?;
-                Ok(())
+                Ok(ControlFlow::Next)
             }
             Statement::Block(statements) => {
-                for statement in statements {
-                    self.interpret_statement(statement)?;
+                let environment = Environment::with_parent(self.environment.clone());
+                self.interpret_block(statements, environment)
+            }
+        }
+    }
+
+    /// Binds the names in `pattern` to the matching parts of `value`, either
+    /// defining them in the current scope or assigning to existing bindings.
+    fn bind(
+        &mut self,
+        pattern: &Pattern,
+        value: Object,
+        define: bool,
+        span: &Span,
+    ) -> Result<(), QclError> {
+        let patterns = match pattern {
+            Pattern::Wildcard => return Ok(()),
+            Pattern::Name(name) | Pattern::Rest(name) => {
+                if define {
+                    self.environment.borrow_mut().define(name.clone(), value);
+                    return Ok(());
+                }
+                if self.environment.borrow_mut().assign(name, value) {
+                    return Ok(());
+                }
+                return Err(QclError::new(
+                    QclErrorType::UndefinedVariableError,
+                    span.clone(),
+                    format!("Cannot assign to undefined variable {}", name),
+                ));
+            }
+            Pattern::Value(_) | Pattern::Range(_, _, _) => {
+                if self.match_pattern(pattern, &value, &mut Vec::new())? {
+                    return Ok(());
+                }
+                return Err(QclError::new(
+                    QclErrorType::MatchError,
+                    span.clone(),
+                    format!("{} does not match the pattern", value),
+                ));
+            }
+            Pattern::Record(fields) => {
+                for (key, pattern) in fields {
+                    let field = map::field(&value, key, span)?;
+                    self.bind(pattern, field, define, span)?;
+                }
+                return Ok(());
+            }
+            Pattern::Tuple(patterns) | Pattern::List(patterns) => patterns,
+        };
+        let Some(values) = list::elements(&value) else {
+            return Err(QclError::new(
+                QclErrorType::TypeError,
+                span.clone(),
+                format!("Cannot destructure {}", value.type_name()),
+            ));
+        };
+        let found = values.len();
+        let Some(values) = align(patterns, values, &value) else {
+            let rest = patterns
+                .iter()
+                .any(|pattern| matches!(pattern, Pattern::Rest(_)));
+            return Err(QclError::new(
+                QclErrorType::ValueError,
+                span.clone(),
+                format!(
+                    "Expected {}{} values to destructure but found {}",
+                    if rest { "at least " } else { "" },
+                    patterns.len() - usize::from(rest),
+                    found
+                ),
+            ));
+        };
+        for (pattern, value) in patterns.iter().zip(values) {
+            self.bind(pattern, value, define, span)?;
+        }
+        Ok(())
+    }
+
+    /// Checks whether `value` fits `pattern`, collecting the names it binds.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Object,
+        bindings: &mut Vec<(String, Object)>,
+    ) -> Result<bool, QclError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Name(name) | Pattern::Rest(name) => {
+                bindings.push((name.clone(), value.clone()));
+                Ok(true)
+            }
+            Pattern::Value(expected) => Ok(*value == self.interpret_expresssion(expected)?),
+            Pattern::Range(start, end, inclusive) => {
+                let start = self.interpret_expresssion(start)?;
+                let end = self.interpret_expresssion(end)?;
+                let below_end = match arithmetic::compare(value, &end) {
+                    Some(Ordering::Less) => true,
+                    Some(Ordering::Equal) => *inclusive,
+                    _ => false,
+                };
+                Ok(arithmetic::compare(value, &start).is_some_and(Ordering::is_ge) && below_end)
+            }
+            Pattern::Tuple(patterns) | Pattern::List(patterns) => {
+                let Some(values) =
+                    list::elements(value).and_then(|values| align(patterns, values, value))
+                else {
+                    return Ok(false);
+                };
+                for (pattern, value) in patterns.iter().zip(values) {
+                    if !self.match_pattern(pattern, &value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Pattern::Record(fields) => {
+                let Object::Map(entries) = value else {
+                    return Ok(false);
+                };
+                for (key, pattern) in fields {
+                    match map::get(entries, key) {
+                        Some(field) if self.match_pattern(pattern, field, bindings)? => (),
+                        _ => return Ok(false),
+                    }
                 }
-                Ok(())
+                Ok(true)
             }
         }
     }
 
-    fn interpret_expresssion(&self, expression: &Spanned<Expression>) -> Result<Object, QclError> {
+    fn interpret_block(
+        &mut self,
+        statements: &[Spanned<Statement>],
+        environment: Environment,
+    ) -> Result<ControlFlow, QclError> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+        let mut result = Ok(ControlFlow::Next);
+        for statement in statements {
+            result = self.interpret_statement(statement);
+            if !matches!(result, Ok(ControlFlow::Next)) {
+                break;
+            }
+        }
+        self.environment = previous;
+        result
+    }
+
+    fn interpret_body(
+        &mut self,
+        body: &Spanned<Statement>,
+        environment: Environment,
+    ) -> Result<ControlFlow, QclError> {
+        match &body.item {
+            Statement::Block(statements) => self.interpret_block(statements, environment),
+            _ => self.interpret_block(std::slice::from_ref(body), environment),
+        }
+    }
+
+    /// Runs `body` with `environment` as the innermost scope.
+    fn interpret_scoped<T>(
+        &mut self,
+        environment: Environment,
+        body: impl FnOnce(&mut Self) -> Result<T, QclError>,
+    ) -> Result<T, QclError> {
+        let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(environment)));
+        let result = body(self);
+        self.environment = previous;
+        result
+    }
+
+    fn interpret_expresssion(
+        &mut self,
+        expression: &Spanned<Expression>,
+    ) -> Result<Object, QclError> {
         match &expression.item {
-            Expression::Number(number) => Ok(Object::Float(*number)),
-            Expression::Name(string) => Ok(Object::Float(123.)),
+            Expression::Integer(number) => Ok(Object::Int(*number)),
+            Expression::BigInteger(number) => Ok(Object::BigInt(Rc::new(number.clone()))),
+            Expression::Float(number) => Ok(Object::Float(*number)),
+            Expression::Quantity(value, terms) => {
+                let number = self.interpret_expresssion(value)?;
+                let number = arithmetic::to_float(&number).expect("Literal is a real number");
+                let unit = self.resolve_unit(terms, &expression.span)?;
+                Ok(units::quantity(number, unit))
+            }
+            Expression::Conversion(value, terms) => {
+                let value = self.interpret_expresssion(value)?;
+                let unit = self.resolve_unit(terms, &expression.span)?;
+                units::convert(value, unit, &expression.span)
+            }
+            Expression::Date(date) => Ok(Object::Date(*date)),
+            Expression::Time(time) => Ok(Object::Time(*time)),
+            Expression::DateTime(datetime) => Ok(Object::DateTime(*datetime)),
+            Expression::Imaginary(number) => Ok(Object::Complex(Complex::new(0.0, *number))),
+            Expression::Decimal(number) => Ok(Object::Decimal(Rc::new(number.clone()))),
+            Expression::Boolean(boolean) => Ok(Object::Bool(*boolean)),
+            Expression::String(string) => Ok(Object::Str(string.as_str().into())),
+            Expression::Interpolation(segments) => {
+                let mut string = String::new();
+                for segment in segments {
+                    match segment {
+                        StringSegment::Text(text) => string.push_str(text),
+                        StringSegment::Value(value, format) => {
+                            let value = self.interpret_expresssion(value)?;
+                            string.push_str(&format.apply(&value, self.settings.rounding));
+                        }
+                    }
+                }
+                Ok(Object::Str(string.into()))
+            }
+            Expression::Name(name) => match self.environment.borrow().get(name) {
+                Some(value) => Ok(value),
+                None => Err(QclError::new(
+                    QclErrorType::UndefinedVariableError,
+                    expression.span.clone(),
+                    format!("Variable {} is not defined", name),
+                )),
+            },
             Expression::Negation(inner) => {
                 let inner = self.interpret_expresssion(inner)?;
-                match inner {
-                    Object::Float(inner) => Ok(Object::Float(-inner)),
-                }
+                arithmetic::negate(inner, &expression.span)
+            }
+            Expression::Not(inner) => {
+                let inner = self.interpret_condition(inner)?;
+                Ok(Object::Bool(!inner))
+            }
+            Expression::And(left, right) => {
+                let left = self.interpret_condition(left)?;
+                Ok(Object::Bool(left && self.interpret_condition(right)?))
+            }
+            Expression::Or(left, right) => {
+                let left = self.interpret_condition(left)?;
+                Ok(Object::Bool(left || self.interpret_condition(right)?))
+            }
+            Expression::Equal(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                Ok(Object::Bool(left == right))
+            }
+            Expression::NotEqual(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                Ok(Object::Bool(left != right))
+            }
+            Expression::Less(left, right) => {
+                self.interpret_comparison(left, right, &expression.span, Ordering::is_lt)
+            }
+            Expression::LessEqual(left, right) => {
+                self.interpret_comparison(left, right, &expression.span, Ordering::is_le)
+            }
+            Expression::Greater(left, right) => {
+                self.interpret_comparison(left, right, &expression.span, Ordering::is_gt)
+            }
+            Expression::GreaterEqual(left, right) => {
+                self.interpret_comparison(left, right, &expression.span, Ordering::is_ge)
             }
             Expression::Addition(left, right) => {
                 let left = self.interpret_expresssion(left)?;
                 let right = self.interpret_expresssion(right)?;
-                match (left, right) {
-                    (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left + right)),
-                }
+                percent::add(left, right, &self.settings, &expression.span)
             }
             Expression::Subtraction(left, right) => {
                 let left = self.interpret_expresssion(left)?;
                 let right = self.interpret_expresssion(right)?;
-                match (left, right) {
-                    (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left - right)),
-                }
+                percent::subtract(left, right, &self.settings, &expression.span)
             }
             Expression::Multiplication(left, right) => {
                 let left = self.interpret_expresssion(left)?;
                 let right = self.interpret_expresssion(right)?;
-                match (left, right) {
-                    (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left * right)),
-                }
+                percent::multiply(left, right, &self.settings, &expression.span)
             }
             Expression::Division(left, right) => {
                 let left = self.interpret_expresssion(left)?;
                 let right = self.interpret_expresssion(right)?;
-                match (left, right) {
-                    (_, Object::Float(right)) if right == 0.0 => Err(QclError::new(
-                        QclErrorType::DivisionByZeroError,
+                percent::divide(left, right, &self.settings, &expression.span)
+            }
+            Expression::FloorDivision(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                arithmetic::floor_divide(left, right, &expression.span)
+            }
+            Expression::Modulo(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                arithmetic::modulo(left, right, &expression.span)
+            }
+            Expression::Power(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                arithmetic::power(left, right, &self.settings, &expression.span)
+            }
+            Expression::Percentage(value) => {
+                let value = self.interpret_expresssion(value)?;
+                percent::percentage(value, &expression.span)
+            }
+            Expression::PercentOf(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                percent::of(left, right, &self.settings, &expression.span)
+            }
+            Expression::AsPercentOf(left, right) => {
+                let left = self.interpret_expresssion(left)?;
+                let right = self.interpret_expresssion(right)?;
+                percent::as_percent_of(left, right, &self.settings, &expression.span)
+            }
+            Expression::Lambda(parameters, body) => {
+                let function = Function {
+                    name: "lambda".to_string(),
+                    parameters: parameters.clone(),
+                    body: body.clone(),
+                    environment: self.environment.clone(),
+                };
+                Ok(Object::Function(Rc::new(function)))
+            }
+            Expression::If(condition, then_branch, else_branch) => {
+                if self.interpret_condition(condition)? {
+                    self.interpret_expresssion(then_branch)
+                } else {
+                    self.interpret_expresssion(else_branch)
+                }
+            }
+            Expression::Range(start, end, step, inclusive) => {
+                let start = self.interpret_expresssion(start)?;
+                let end = self.interpret_expresssion(end)?;
+                if !arithmetic::is_number(&start) || !arithmetic::is_number(&end) {
+                    return Err(arithmetic::operand_error(
+                        "build a range from",
+                        &start,
+                        &end,
+                        &expression.span,
+                    ));
+                }
+                let step = match step {
+                    Some(step) => self.interpret_expresssion(step)?,
+                    None => Object::Int(1),
+                };
+                if !arithmetic::is_number(&step) {
+                    return Err(QclError::new(
+                        QclErrorType::TypeError,
                         expression.span.clone(),
-                        ":(".to_string(),
-                    )),
-                    (Object::Float(left), Object::Float(right)) => Ok(Object::Float(left / right)),
+                        format!("Expected a number as step but found {}", step.type_name()),
+                    ));
+                }
+                if arithmetic::is_zero(&step) {
+                    return Err(QclError::new(
+                        QclErrorType::ValueError,
+                        expression.span.clone(),
+                        "Range step cannot be zero".to_string(),
+                    ));
+                }
+                Ok(Object::Range(Range {
+                    start: Box::new(start),
+                    end: Box::new(end),
+                    step: Box::new(step),
+                    inclusive: *inclusive,
+                }))
+            }
+            Expression::List(elements) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.interpret_expresssion(element)?);
+                }
+                Ok(list::list(values))
+            }
+            Expression::Tuple(elements) => {
+                let mut values = Vec::new();
+                for element in elements {
+                    values.push(self.interpret_expresssion(element)?);
+                }
+                Ok(list::tuple(values))
+            }
+            Expression::Comprehension(element, name, iterable, condition) => {
+                let values = self.interpret_expresssion(iterable)?;
+                let mut elements = Vec::new();
+                for index in 0.. {
+                    let value = match list::element(&values, index, &iterable.span)? {
+                        Some(value) => value,
+                        None => break,
+                    };
+                    let mut environment = Environment::with_parent(self.environment.clone());
+                    environment.define(name.clone(), value);
+                    let element = self.interpret_scoped(environment, |interpreter| {
+                        if let Some(condition) = condition {
+                            if !interpreter.interpret_condition(condition)? {
+                                return Ok(None);
+                            }
+                        }
+                        interpreter.interpret_expresssion(element).map(Some)
+                    })?;
+                    elements.extend(element);
                 }
+                Ok(list::list(elements))
             }
+            Expression::Index(values, index) => {
+                let values = self.interpret_expresssion(values)?;
+                let index = self.interpret_expresssion(index)?;
+                list::index(&values, &index, &expression.span)
+            }
+            Expression::Map(entries) => {
+                let mut values = Vec::new();
+                for (key, value) in entries {
+                    values.push((key.clone(), self.interpret_expresssion(value)?));
+                }
+                Ok(map::map(values))
+            }
+            Expression::Field(value, name) => {
+                let value = self.interpret_expresssion(value)?;
+                map::field(&value, name, &expression.span)
+            }
+            Expression::Match(value, arms) => {
+                let value = self.interpret_expresssion(value)?;
+                for arm in arms {
+                    let mut bindings = Vec::new();
+                    if !self.match_pattern(&arm.pattern, &value, &mut bindings)? {
+                        continue;
+                    }
+                    let mut environment = Environment::with_parent(self.environment.clone());
+                    for (name, value) in bindings {
+                        environment.define(name, value);
+                    }
+                    let result = self.interpret_scoped(environment, |interpreter| {
+                        if let Some(guard) = &arm.guard {
+                            if !interpreter.interpret_guard(guard)? {
+                                return Ok(None);
+                            }
+                        }
+                        interpreter.interpret_expresssion(&arm.body).map(Some)
+                    })?;
+                    if let Some(result) = result {
+                        return Ok(result);
+                    }
+                }
+                Err(QclError::new(
+                    QclErrorType::MatchError,
+                    expression.span.clone(),
+                    format!("No pattern matches {}", value),
+                ))
+            }
+            Expression::Sum(name, values, term) => self.interpret_series(
+                name,
+                values,
+                term,
+                arithmetic::add,
+                Object::Int(0),
+                &expression.span,
+            ),
+            Expression::Product(name, values, term) => self.interpret_series(
+                name,
+                values,
+                term,
+                arithmetic::multiply,
+                Object::Int(1),
+                &expression.span,
+            ),
+            Expression::Call(callee, arguments) => {
+                let callee = self.interpret_expresssion(callee)?;
+                let mut values = Vec::new();
+                for argument in arguments {
+                    values.push(self.interpret_expresssion(argument)?);
+                }
+                self.call(callee, values, &expression.span)
+            }
+        }
+    }
+
+    /// Evaluates `term` once for each of `values`, with `name` bound to the
+    /// value in a fresh scope, and combines the results from left to right.
+    fn interpret_series(
+        &mut self,
+        name: &str,
+        values: &Spanned<Expression>,
+        term: &Spanned<Expression>,
+        combine: fn(Object, Object, &Span) -> Result<Object, QclError>,
+        empty: Object,
+        span: &Span,
+    ) -> Result<Object, QclError> {
+        let iterable = self.interpret_expresssion(values)?;
+        let mut result = None;
+        for index in 0.. {
+            let Some(value) = list::element(&iterable, index, &values.span)? else {
+                break;
+            };
+            let mut environment = Environment::with_parent(self.environment.clone());
+            environment.define(name.to_string(), value);
+            let value = self.interpret_scoped(environment, |interpreter| {
+                interpreter.interpret_expresssion(term)
+            })?;
+            result = Some(match result {
+                Some(result) => combine(result, value, span)?,
+                None => value,
+            });
+        }
+        Ok(result.unwrap_or(empty))
+    }
+
+    fn interpret_condition(&mut self, expression: &Spanned<Expression>) -> Result<bool, QclError> {
+        match self.interpret_expresssion(expression)? {
+            Object::Bool(boolean) => Ok(boolean),
+            value => Err(QclError::new(
+                QclErrorType::TypeError,
+                expression.span.clone(),
+                format!("Expected a bool but found {}", value.type_name()),
+            )),
+        }
+    }
+
+    fn resolve_unit(&self, terms: &[(String, i32)], span: &Span) -> Result<Unit, QclError> {
+        Unit::resolve(terms, &self.rates)
+            .map_err(|message| QclError::new(QclErrorType::ValueError, span.clone(), message))
+    }
+
+    fn interpret_comparison(
+        &mut self,
+        left: &Spanned<Expression>,
+        right: &Spanned<Expression>,
+        span: &Span,
+        test: fn(Ordering) -> bool,
+    ) -> Result<Object, QclError> {
+        let left = self.interpret_expresssion(left)?;
+        let right = self.interpret_expresssion(right)?;
+        order(&left, &right, span, test).map(Object::Bool)
+    }
+
+    /// Evaluates the guard of a match arm. A comparison at the top of the
+    /// guard that cannot be made, such as `n < 0` when the value is a list,
+    /// fails the guard instead of raising, so that later arms are tried.
+    /// Errors raised while evaluating its operands still propagate.
+    fn interpret_guard(&mut self, guard: &Spanned<Expression>) -> Result<bool, QclError> {
+        let (left, right, test): (_, _, fn(Ordering) -> bool) = match &guard.item {
+            Expression::Less(left, right) => (left, right, Ordering::is_lt),
+            Expression::LessEqual(left, right) => (left, right, Ordering::is_le),
+            Expression::Greater(left, right) => (left, right, Ordering::is_gt),
+            Expression::GreaterEqual(left, right) => (left, right, Ordering::is_ge),
+            _ => return self.interpret_condition(guard),
+        };
+        let left = self.interpret_expresssion(left)?;
+        let right = self.interpret_expresssion(right)?;
+        Ok(order(&left, &right, &guard.span, test).unwrap_or(false))
+    }
+
+    fn call(
+        &mut self,
+        callee: Object,
+        arguments: Vec<Object>,
+        span: &Span,
+    ) -> Result<Object, QclError> {
+        let function = match callee {
+            Object::Function(function) => function,
+            Object::Builtin(builtin) => {
+                if arguments.len() != builtin.arity {
+                    return Err(QclError::new(
+                        QclErrorType::ArityError,
+                        span.clone(),
+                        format!(
+                            "{} expects {} arguments but got {}",
+                            builtin.name,
+                            builtin.arity,
+                            arguments.len()
+                        ),
+                    ));
+                }
+                return (builtin.function)(arguments, &self.settings, span);
+            }
+            callee => {
+                return Err(QclError::new(
+                    QclErrorType::TypeError,
+                    span.clone(),
+                    format!("Cannot call {}", callee.type_name()),
+                ))
+            }
+        };
+        if arguments.len() != function.parameters.len() {
+            return Err(QclError::new(
+                QclErrorType::ArityError,
+                span.clone(),
+                format!(
+                    "{} expects {} arguments but got {}",
+                    function.name,
+                    function.parameters.len(),
+                    arguments.len()
+                ),
+            ));
+        }
+        let mut environment = Environment::with_parent(function.environment.clone());
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            environment.define(parameter.clone(), argument);
+        }
+        match self.interpret_body(&function.body, environment)? {
+            ControlFlow::Return(value) => Ok(value),
+            _ => Ok(Object::Nil),
+        }
+    }
+}
+
+/// Orders two values for `<`, `<=`, `>` or `>=`, passing the ordering to
+/// `test`.
+fn order(
+    left: &Object,
+    right: &Object,
+    span: &Span,
+    test: fn(Ordering) -> bool,
+) -> Result<bool, QclError> {
+    // Complex numbers have no order, even when they are equal.
+    if matches!(left, Object::Complex(_)) || matches!(right, Object::Complex(_)) {
+        return Err(arithmetic::operand_error("compare", left, right, span));
+    }
+    match arithmetic::compare(left, right) {
+        Some(ordering) => Ok(test(ordering)),
+        // Only NaN leaves two real numbers unordered.
+        None if arithmetic::is_number(left) && arithmetic::is_number(right) => Ok(false),
+        None if units::is_quantity(left) || units::is_quantity(right) => {
+            Err(units::dimension_error("compare", left, right, span))
+        }
+        None => Err(arithmetic::operand_error("compare", left, right, span)),
+    }
+}
+
+/// Lines up the elements of a list or tuple with the patterns of a sequence
+/// pattern, gathering those taken by a `..rest` pattern into one value of the
+/// same kind. Returns None if the number of elements does not fit.
+fn align(patterns: &[Pattern], mut values: Vec<Object>, value: &Object) -> Option<Vec<Object>> {
+    let rest = patterns
+        .iter()
+        .position(|pattern| matches!(pattern, Pattern::Rest(_)));
+    let Some(position) = rest else {
+        return (values.len() == patterns.len()).then_some(values);
+    };
+    let end = values.len().checked_sub(patterns.len() - position - 1)?;
+    if end < position {
+        return None;
+    }
+    let collected: Vec<Object> = values.drain(position..end).collect();
+    let collected = match value {
+        Object::Tuple(_) => list::tuple(collected),
+        _ => list::list(collected),
+    };
+    values.insert(position, collected);
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Runs `source` and returns the value it leaves in `result`.
+    fn run(source: &str) -> Result<Object, QclError> {
+        let source = source.to_string();
+        let tokens = Lexer::new(source.clone()).lex().unwrap();
+        let ast = Parser::new(source, tokens).parse().unwrap();
+        let mut interpreter = Interpreter::new(ast, Rates::new());
+        let environment = interpreter.environment.clone();
+        environment
+            .borrow_mut()
+            .define("result".to_string(), Object::Nil);
+        interpreter.interpret()?;
+        let result = environment.borrow().get("result").unwrap();
+        Ok(result)
+    }
+
+    fn result_of(source: &str) -> Object {
+        run(source).unwrap()
+    }
+
+    fn evaluate(expression: &str) -> Object {
+        result_of(&format!("{{\n    result = {}\n}}\n", expression))
+    }
+
+    fn error_of(expression: &str) -> QclError {
+        match run(&format!("{{\n    result = {}\n}}\n", expression)) {
+            Ok(result) => panic!("Expected an error but found {}", result),
+            Err(error) => error,
+        }
+    }
+
+    #[test]
+    fn prints_floats_that_read_back_exactly() {
+        let root = evaluate("2^0.5");
+        assert_eq!(root.to_string().parse::<f64>(), Ok(2f64.sqrt()));
+        assert_eq!(evaluate("0.1 + 0.2").to_string(), "0.30000000000000004");
+    }
+
+    #[test]
+    fn guards_that_do_not_apply_skip_the_arm() {
+        let result = result_of(
+            "{
+    fn classify(value) {
+        return match value {
+            0 => \"zero\"
+            n if n < 0 => \"negative\"
+            [x, y] => \"pair\"
+            _ => \"other\"
         }
     }
+    result = [classify(0), classify(-3), classify([1, 2]), classify(\"a\")]
+}
+",
+        );
+        assert_eq!(
+            result.to_string(),
+            "[\"zero\", \"negative\", \"pair\", \"other\"]"
+        );
+    }
+
+    #[test]
+    fn rejects_powers_too_large_to_compute() {
+        let error = error_of("2^10000000");
+        assert_eq!(*error.error_type(), QclErrorType::OverflowError);
+        let error = error_of("12345678901234567890^100000");
+        assert_eq!(*error.error_type(), QclErrorType::OverflowError);
+        assert_eq!(evaluate("(-1)^10000001"), Object::Int(-1));
+        assert_eq!(evaluate("2^64").to_string(), "18446744073709551616");
+    }
+
+    #[test]
+    fn rejects_decimals_too_large_to_compute() {
+        let error = error_of("0.0000001d ^ 1000000000");
+        assert_eq!(*error.error_type(), QclErrorType::OverflowError);
+        let error =
+            run("{\n    set precision = 4294967295\n    result = 1d / 3.5d\n}\n").unwrap_err();
+        assert_eq!(*error.error_type(), QclErrorType::ValueError);
+    }
+
+    #[test]
+    fn reads_step_as_a_name_outside_ranges() {
+        let result =
+            result_of("{\n    let step = 0.5\n    result = [x for x in 0..=2 step step]\n}\n");
+        assert_eq!(result.to_string(), "[0, 0.5, 1, 1.5, 2]");
+    }
+
+    #[test]
+    fn reads_rates_as_a_name_without_a_path() {
+        let result = result_of("{\n    let rates = [0.05, 0.07]\n    result = rates[1]\n}\n");
+        assert_eq!(result, Object::Float(0.07));
+    }
+
+    #[test]
+    fn reads_of_as_a_name_outside_percentages() {
+        let result = result_of("{\n    let of = 3\n    result = [of * 2, 50% of 80, 10 % of]\n}\n");
+        assert_eq!(result.to_string(), "[6, 40, 1]");
+    }
+
+    #[test]
+    fn raises_errors_from_guard_operands() {
+        let error = error_of("match 3 { n if n + \"x\" > 0 => \"pos\", _ => \"other\" }");
+        assert_eq!(*error.error_type(), QclErrorType::TypeError);
+    }
+
+    #[test]
+    fn binds_unary_minus_looser_than_powers() {
+        assert_eq!(evaluate("-2^2"), Object::Int(-4));
+        assert_eq!(evaluate("-2^2 == -4"), Object::Bool(true));
+        assert_eq!(evaluate("(-2)^2"), Object::Int(4));
+    }
+
+    #[test]
+    fn groups_powers_to_the_right() {
+        assert_eq!(evaluate("2^3^2"), Object::Int(512));
+        assert_eq!(evaluate("(2^3)^2"), Object::Int(64));
+    }
+
+    #[test]
+    fn floors_integer_division_and_modulo() {
+        assert_eq!(evaluate("7 // 2"), Object::Int(3));
+        assert_eq!(evaluate("-7 // 2"), Object::Int(-4));
+        assert_eq!(evaluate("-7 % 3"), Object::Int(2));
+    }
+
+    #[test]
+    fn promotes_overflowing_integers_exactly() {
+        let product = evaluate("2^62 * 4");
+        assert!(matches!(product, Object::BigInt(_)));
+        assert_eq!(product.to_string(), "18446744073709551616");
+        assert_eq!(
+            evaluate("9223372036854775807 + 1").to_string(),
+            "9223372036854775808"
+        );
+        assert_eq!(evaluate("2^62 * 4 // 4 == 2^62"), Object::Bool(true));
+    }
+
+    #[test]
+    fn divides_exactly_in_rational_mode() {
+        assert_eq!(evaluate("7 / 2"), Object::Float(3.5));
+        let result = result_of(
+            "{\n    set division = rational\n    result = [1/3 + 1/6, 2/4 * 2, 1/3 == 2/6]\n}\n",
+        );
+        assert_eq!(result.to_string(), "[1/2, 1, true]");
+    }
+
+    #[test]
+    fn tells_percentages_from_modulo() {
+        assert_eq!(evaluate("10 % 3"), Object::Int(1));
+        assert_eq!(evaluate("10 %3"), Object::Int(1));
+        assert_eq!(evaluate("50% of 80"), Object::Int(40));
+        assert_eq!(evaluate("200 + 10%"), Object::Int(220));
+        let result = result_of("{\n    let x = 5\n    result = [x%, (x)%, x % 3]\n}\n");
+        assert_eq!(result.to_string(), "[5%, 5%, 2]");
+    }
+
+    #[test]
+    fn indexes_and_slices_from_either_end() {
+        let result = result_of(
+            "{\n    let xs = [1, 2, 3, 4, 5]\n    result = [xs[-1], xs[1..3], xs[-2..=-1], xs[0..=1]]\n}\n",
+        );
+        assert_eq!(result.to_string(), "[5, [2, 3], [4, 5], [1, 2]]");
+        let error = error_of("[1, 2][-3]");
+        assert_eq!(*error.error_type(), QclErrorType::IndexError);
+        let error = error_of("[1, 2][1..5]");
+        assert_eq!(*error.error_type(), QclErrorType::IndexError);
+    }
+
+    #[test]
+    fn reads_map_fields_and_iterates_keys() {
+        let result = result_of(
+            "{\n    let m = {name: \"a\", \"b c\": 2}\n    result = [m.name, [k for k in m]]\n}\n",
+        );
+        assert_eq!(result.to_string(), "[\"a\", [\"name\", \"b c\"]]");
+        let error = error_of("{name: 1}.age");
+        assert_eq!(*error.error_type(), QclErrorType::KeyError);
+    }
+
+    #[test]
+    fn gathers_rest_elements_when_destructuring() {
+        let result = result_of(
+            "{\n    let [first, ..rest] = [1, 2, 3]\n    let (a, ..middle, z) = (1, 2, 3, 4)\n    result = [first, rest, middle, z]\n}\n",
+        );
+        assert_eq!(result.to_string(), "[1, [2, 3], (2, 3), 4]");
+        let error = run("{\n    let [a, b, ..rest] = [1]\n}\n").unwrap_err();
+        assert_eq!(*error.error_type(), QclErrorType::ValueError);
+    }
+
+    #[test]
+    fn scopes_series_indices_to_their_terms() {
+        let result = result_of(
+            "{\n    let k = 100\n    result = [sum(k = 1..=3, k^2), prod(k = 1..=4, sum(j = 1..=k, 1)), k]\n}\n",
+        );
+        assert_eq!(result.to_string(), "[14, 24, 100]");
+        let error = error_of("sum(j = 1..=3, j) + j");
+        assert_eq!(*error.error_type(), QclErrorType::UndefinedVariableError);
+    }
+
+    #[test]
+    fn refuses_to_order_complex_numbers() {
+        let error = error_of("1 + 2i < 3");
+        assert_eq!(*error.error_type(), QclErrorType::TypeError);
+        let error = error_of("1i <= 1i");
+        assert_eq!(*error.error_type(), QclErrorType::TypeError);
+        assert_eq!(evaluate("1i == 1i"), Object::Bool(true));
+    }
+
+    #[test]
+    fn prints_complex_parts_like_floats() {
+        let sum = evaluate("0.1 + 0.2");
+        let complex = evaluate("0.1 + 0.2 + 2^0.5 * 1i");
+        assert_eq!(
+            complex.to_string(),
+            format!("{}+{}i", sum, evaluate("2^0.5"))
+        );
+    }
+
+    #[test]
+    fn rounds_floats_to_any_number_of_places() {
+        assert_eq!(evaluate("round(1.25, 400)"), Object::Float(1.25));
+        assert_eq!(evaluate("round(1.25, 17)"), Object::Float(1.25));
+        assert_eq!(evaluate("round(10.0^300, 10)"), Object::Float(1e300));
+        assert_eq!(evaluate("round(2.675, 1)"), Object::Float(2.7));
+        let error = error_of("round(1.25, 4294967297)");
+        assert_eq!(*error.error_type(), QclErrorType::ValueError);
+    }
 }
@@ -1,6 +1,7 @@
+use crate::format::Format;
 use crate::qcl_error::{QclError, QclErrorType};
 use crate::span::{Span, Spanned};
-use crate::token::Token;
+use crate::token::{StringPart, Token};
 use std::rc::Rc;
 
 pub struct Lexer {
@@ -18,6 +19,17 @@ impl Lexer {
         }
     }
 
+    /// Lexes only the characters from `start` up to `end` of `source`, as for
+    /// the expressions interpolated into strings, so that spans still point
+    /// into the whole source.
+    pub fn with_range(source: Rc<String>, start: usize, end: usize) -> Self {
+        Lexer {
+            chars: source.chars().take(end).collect(),
+            index: start,
+            source,
+        }
+    }
+
     pub fn lex(&mut self) -> Result<Vec<Spanned<Token>>, QclError> {
         let mut tokens = Vec::new();
         loop {
@@ -39,12 +51,15 @@ impl Lexer {
                     self.advance();
                     return self.next_token();
                 }
-                if ch.is_digit(10) {
+                if ch.is_ascii_digit() {
                     return self.next_number();
                 }
                 if ch.is_alphabetic() || *ch == '_' {
                     return self.next_name();
                 }
+                if *ch == '"' {
+                    return self.next_string();
+                }
                 let token = match ch {
                     '\n' => Spanned::new(
                         Token::Newline,
@@ -54,6 +69,14 @@ impl Lexer {
                         Token::Plus,
                         Span::new(self.source.clone(), self.index, self.index),
                     ),
+                    '-' if self.peek_next() == Some(&'>') => {
+                        let token = Spanned::new(
+                            Token::Arrow,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
                     '-' => Spanned::new(
                         Token::Minus,
                         Span::new(self.source.clone(), self.index, self.index),
@@ -62,10 +85,113 @@ impl Lexer {
                         Token::Star,
                         Span::new(self.source.clone(), self.index, self.index),
                     ),
+                    '/' if self.peek_next() == Some(&'/') => {
+                        let token = Spanned::new(
+                            Token::DoubleSlash,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
                     '/' => Spanned::new(
                         Token::Slash,
                         Span::new(self.source.clone(), self.index, self.index),
                     ),
+                    '%' if self.is_percentage() => Spanned::new(
+                        Token::Percentage,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '%' => Spanned::new(
+                        Token::Percent,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '^' => Spanned::new(
+                        Token::Caret,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '=' if self.peek_next() == Some(&'=') => {
+                        let token = Spanned::new(
+                            Token::EqualEqual,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '=' if self.peek_next() == Some(&'>') => {
+                        let token = Spanned::new(
+                            Token::FatArrow,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '=' => Spanned::new(
+                        Token::Equal,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '!' if self.peek_next() == Some(&'=') => {
+                        let token = Spanned::new(
+                            Token::BangEqual,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '<' if self.peek_next() == Some(&'=') => {
+                        let token = Spanned::new(
+                            Token::LessEqual,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '<' => Spanned::new(
+                        Token::Less,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '>' if self.peek_next() == Some(&'=') => {
+                        let token = Spanned::new(
+                            Token::GreaterEqual,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '>' => Spanned::new(
+                        Token::Greater,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    ',' => Spanned::new(
+                        Token::Comma,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    '.' if self.peek_next() == Some(&'.')
+                        && self.chars.get(self.index + 2) == Some(&'=') =>
+                    {
+                        let token = Spanned::new(
+                            Token::DotDotEqual,
+                            Span::new(self.source.clone(), self.index, self.index + 2),
+                        );
+                        self.advance();
+                        self.advance();
+                        token
+                    }
+                    '.' if self.peek_next() == Some(&'.') => {
+                        let token = Spanned::new(
+                            Token::DotDot,
+                            Span::new(self.source.clone(), self.index, self.index + 1),
+                        );
+                        self.advance();
+                        token
+                    }
+                    '.' => Spanned::new(
+                        Token::Dot,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    ':' => Spanned::new(
+                        Token::Colon,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
                     '(' => Spanned::new(
                         Token::LeftParen,
                         Span::new(self.source.clone(), self.index, self.index),
@@ -74,6 +200,14 @@ impl Lexer {
                         Token::RightParen,
                         Span::new(self.source.clone(), self.index, self.index),
                     ),
+                    '[' => Spanned::new(
+                        Token::LeftBracket,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
+                    ']' => Spanned::new(
+                        Token::RightBracket,
+                        Span::new(self.source.clone(), self.index, self.index),
+                    ),
                     '{' => Spanned::new(
                         Token::LeftCurly,
                         Span::new(self.source.clone(), self.index, self.index),
@@ -103,22 +237,154 @@ impl Lexer {
         Ok(token)
     }
 
+    pub fn next_string(&mut self) -> Result<Spanned<Token>, QclError> {
+        let start_index = self.index;
+        self.advance();
+        let mut parts = Vec::new();
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                Some('"') => break,
+                Some('\n') | None => {
+                    return Err(QclError::new(
+                        QclErrorType::SyntaxError,
+                        Span::new(self.source.clone(), start_index, self.index),
+                        "Unterminated string".to_string(),
+                    ))
+                }
+                Some('\\') => string.push(self.next_escape()?),
+                Some('{') => {
+                    if !string.is_empty() {
+                        parts.push(StringPart::Text(std::mem::take(&mut string)));
+                    }
+                    parts.push(self.next_interpolation()?);
+                    continue;
+                }
+                Some(ch) => string.push(*ch),
+            }
+            self.advance();
+        }
+        self.advance();
+        let span = Span::new(self.source.clone(), start_index, self.index - 1);
+        if parts.is_empty() {
+            return Ok(Spanned::new(Token::String(string), span));
+        }
+        if !string.is_empty() {
+            parts.push(StringPart::Text(string));
+        }
+        Ok(Spanned::new(Token::InterpolatedString(parts), span))
+    }
+
+    /// Reads the escape sequence starting at the current backslash, leaving
+    /// the index on its last character.
+    fn next_escape(&mut self) -> Result<char, QclError> {
+        let start_index = self.index;
+        self.advance();
+        let escaped = match self.peek() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some(ch @ ('\\' | '"' | '{' | '}')) => Some(*ch),
+            Some('u') if self.peek_next() == Some(&'{') => {
+                self.advance();
+                let mut digits = String::new();
+                while let Some(ch) = self.peek_next().filter(|ch| ch.is_ascii_hexdigit()) {
+                    digits.push(*ch);
+                    self.advance();
+                }
+                self.advance();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| self.peek() == Some(&'}'))
+                    .and_then(char::from_u32)
+            }
+            _ => None,
+        };
+        escaped.ok_or_else(|| {
+            QclError::new(
+                QclErrorType::SyntaxError,
+                Span::new(self.source.clone(), start_index, self.index),
+                "Invalid escape sequence".to_string(),
+            )
+        })
+    }
+
+    /// Finds the end of the `{value}` or `{value:format}` starting at the
+    /// current brace, leaving the index after the closing brace. The format is
+    /// whatever follows the last top-level colon, if it is a valid one.
+    fn next_interpolation(&mut self) -> Result<StringPart, QclError> {
+        let start_index = self.index;
+        self.advance();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut colon = None;
+        loop {
+            match self.peek() {
+                Some('\n') | None => {
+                    return Err(QclError::new(
+                        QclErrorType::SyntaxError,
+                        Span::new(self.source.clone(), start_index, self.index),
+                        "Unterminated interpolation".to_string(),
+                    ))
+                }
+                Some('\\') if in_string => self.advance(),
+                Some('"') => in_string = !in_string,
+                Some('(' | '[' | '{') if !in_string => depth += 1,
+                Some(')' | ']') if !in_string => depth -= 1,
+                Some('}') if !in_string && depth == 0 => break,
+                Some('}') if !in_string => depth -= 1,
+                Some(':') if !in_string && depth == 0 => colon = Some(self.index),
+                _ => (),
+            }
+            self.advance();
+        }
+        let end_index = self.index;
+        self.advance();
+        if let Some(colon) = colon {
+            let spec: String = self.chars[colon + 1..end_index].iter().collect();
+            if let Some(format) = Format::parse(&spec) {
+                return Ok(StringPart::Value {
+                    start: start_index + 1,
+                    end: colon,
+                    format,
+                });
+            }
+        }
+        Ok(StringPart::Value {
+            start: start_index + 1,
+            end: end_index,
+            format: Format::default(),
+        })
+    }
+
     pub fn next_number(&mut self) -> Result<Spanned<Token>, QclError> {
         let start_index = self.index;
         let mut string = String::new();
         while let Some(ch) = self.peek() {
-            if ch.is_digit(10) {
+            if ch.is_ascii_digit() {
                 string.push(*ch);
             } else {
                 break;
             }
             self.advance();
         }
-        if let Some('.') = self.peek() {
+        if string.len() == 4 && self.follows("-dd-dd") {
+            return Ok(self.next_pattern(start_index, string, "-dd-dd", Token::Date));
+        }
+        if string.len() <= 2 && self.follows(":dd") {
+            let pattern = if self.follows(":dd:dd") {
+                ":dd:dd"
+            } else {
+                ":dd"
+            };
+            return Ok(self.next_pattern(start_index, string, pattern, Token::Time));
+        }
+        if self.peek() == Some(&'.') && self.peek_next().is_some_and(char::is_ascii_digit) {
             self.advance();
             string.push('.');
             while let Some(ch) = self.peek() {
-                if ch.is_digit(10) {
+                if ch.is_ascii_digit() {
                     string.push(*ch);
                 } else {
                     break;
@@ -126,17 +392,59 @@ impl Lexer {
                 self.advance();
             }
         }
+        if self.peek() == Some(&'d') && !self.peek_next().is_some_and(|ch| ch.is_alphanumeric()) {
+            self.advance();
+            return Ok(Spanned::new(
+                Token::Decimal(string),
+                Span::new(self.source.clone(), start_index, self.index - 1),
+            ));
+        }
+        if self.peek() == Some(&'i') && !self.peek_next().is_some_and(|ch| ch.is_alphanumeric()) {
+            self.advance();
+            return Ok(Spanned::new(
+                Token::Imaginary(string),
+                Span::new(self.source.clone(), start_index, self.index - 1),
+            ));
+        }
         Ok(Spanned::new(
             Token::Number(string),
             Span::new(self.source.clone(), start_index, self.index - 1),
         ))
     }
 
+    /// Checks whether the upcoming characters match `pattern`, where `d`
+    /// stands for any digit, and are not followed by another digit.
+    fn follows(&self, pattern: &str) -> bool {
+        let mut chars = self.chars[self.index..].iter();
+        pattern.chars().all(|expected| match chars.next() {
+            Some(ch) if expected == 'd' => ch.is_ascii_digit(),
+            Some(ch) => *ch == expected,
+            None => false,
+        }) && !chars.next().is_some_and(char::is_ascii_digit)
+    }
+
+    fn next_pattern(
+        &mut self,
+        start_index: usize,
+        mut string: String,
+        pattern: &str,
+        token: fn(String) -> Token,
+    ) -> Spanned<Token> {
+        for _ in pattern.chars() {
+            string.push(self.chars[self.index]);
+            self.advance();
+        }
+        Spanned::new(
+            token(string),
+            Span::new(self.source.clone(), start_index, self.index - 1),
+        )
+    }
+
     pub fn next_name(&mut self) -> Result<Spanned<Token>, QclError> {
         let start_index = self.index;
         let mut string = String::new();
         while let Some(ch) = self.peek() {
-            if ch.is_alphabetic() || *ch == '_' {
+            if ch.is_alphanumeric() || *ch == '_' {
                 string.push(*ch);
             } else {
                 break;
@@ -144,10 +452,31 @@ impl Lexer {
             self.advance();
         }
         let span = Span::new(self.source.clone(), start_index, self.index - 1);
-        match string.as_str() {
-            "print" => Ok(Spanned::new(Token::Print, span)),
-            _ => Ok(Spanned::new(Token::Identifier(string), span)),
-        }
+        let token = keyword(&string).unwrap_or(Token::Identifier(string));
+        Ok(Spanned::new(token, span))
+    }
+
+    /// A `%` written directly after an operand and not followed by another
+    /// operand marks a percentage, as in `200 + 10%`, rather than a modulo.
+    /// The word `of` after it continues the percentage, as in `10% of 80`.
+    fn is_percentage(&self) -> bool {
+        let attached = self.index > 0
+            && matches!(self.chars[self.index - 1], ch if ch.is_alphanumeric() || matches!(ch, '_' | ')' | ']'));
+        let mut rest = self.chars[self.index + 1..]
+            .iter()
+            .skip_while(|ch| **ch == ' ')
+            .peekable();
+        let operand = match rest.peek() {
+            Some(ch) if ch.is_ascii_digit() || matches!(ch, '(' | '.' | '"') => true,
+            Some(ch) if ch.is_alphabetic() || **ch == '_' => {
+                let word: String = rest
+                    .take_while(|ch| ch.is_alphanumeric() || **ch == '_')
+                    .collect();
+                keyword(&word).is_none() && word != "of"
+            }
+            _ => false,
+        };
+        attached && !operand
     }
 
     pub fn advance(&mut self) {
@@ -157,4 +486,35 @@ impl Lexer {
     pub fn peek(&self) -> Option<&char> {
         self.chars.get(self.index)
     }
+
+    pub fn peek_next(&self) -> Option<&char> {
+        self.chars.get(self.index + 1)
+    }
+}
+
+fn keyword(name: &str) -> Option<Token> {
+    match name {
+        "print" => Some(Token::Print),
+        "let" => Some(Token::Let),
+        "fn" => Some(Token::Fn),
+        "return" => Some(Token::Return),
+        "true" => Some(Token::True),
+        "false" => Some(Token::False),
+        "and" => Some(Token::And),
+        "or" => Some(Token::Or),
+        "not" => Some(Token::Not),
+        "if" => Some(Token::If),
+        "then" => Some(Token::Then),
+        "else" => Some(Token::Else),
+        "while" => Some(Token::While),
+        "for" => Some(Token::For),
+        "in" => Some(Token::In),
+        "break" => Some(Token::Break),
+        "continue" => Some(Token::Continue),
+        "set" => Some(Token::Set),
+        "to" => Some(Token::To),
+        "as" => Some(Token::As),
+        "match" => Some(Token::Match),
+        _ => None,
+    }
 }
@@ -0,0 +1,122 @@
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::span::Span;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn list(values: Vec<Object>) -> Object {
+    Object::List(Rc::new(RefCell::new(values)))
+}
+
+pub fn tuple(values: Vec<Object>) -> Object {
+    Object::Tuple(Rc::new(values))
+}
+
+/// Copies out the elements of a list or tuple.
+pub fn elements(values: &Object) -> Option<Vec<Object>> {
+    match values {
+        Object::List(values) => Some(values.borrow().clone()),
+        Object::Tuple(values) => Some(values.to_vec()),
+        _ => None,
+    }
+}
+
+/// Returns the element at `index` of anything that can be iterated over, or
+/// None past the end. Iterating over a map gives its keys.
+pub fn element(values: &Object, index: usize, span: &Span) -> Result<Option<Object>, QclError> {
+    match values {
+        Object::Range(range) => range.get(index, span),
+        Object::List(values) => Ok(values.borrow().get(index).cloned()),
+        Object::Tuple(values) => Ok(values.get(index).cloned()),
+        Object::Map(entries) => Ok(entries
+            .get(index)
+            .map(|(key, _)| Object::Str(key.as_str().into()))),
+        values => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot iterate over {}", values.type_name()),
+        )),
+    }
+}
+
+/// Evaluates `values[index]`, where a negative index counts from the end and
+/// a range of indices selects a slice.
+pub fn index(values: &Object, index: &Object, span: &Span) -> Result<Object, QclError> {
+    match values {
+        Object::List(list_values) => select(&list_values.borrow(), values, index, span, list),
+        Object::Tuple(tuple_values) => select(tuple_values, values, index, span, tuple),
+        values => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot index {}", values.type_name()),
+        )),
+    }
+}
+
+fn select(
+    elements: &[Object],
+    values: &Object,
+    index: &Object,
+    span: &Span,
+    slice: fn(Vec<Object>) -> Object,
+) -> Result<Object, QclError> {
+    let length = elements.len() as i64;
+    match index {
+        Object::Range(range) => {
+            if *range.step != Object::Int(1) {
+                return Err(QclError::new(
+                    QclErrorType::TypeError,
+                    span.clone(),
+                    "Cannot slice with a stepped range".to_string(),
+                ));
+            }
+            let start = position(&range.start, length, span)?;
+            let end = position(&range.end, length, span)? + i64::from(range.inclusive);
+            if start < 0 || start > end || end > length {
+                return Err(QclError::new(
+                    QclErrorType::IndexError,
+                    span.clone(),
+                    format!(
+                        "Slice {} is out of bounds for a {} of length {}",
+                        index,
+                        values.type_name(),
+                        length
+                    ),
+                ));
+            }
+            Ok(slice(elements[start as usize..end as usize].to_vec()))
+        }
+        index => {
+            let position = position(index, length, span)?;
+            usize::try_from(position)
+                .ok()
+                .and_then(|position| elements.get(position))
+                .cloned()
+                .ok_or_else(|| {
+                    QclError::new(
+                        QclErrorType::IndexError,
+                        span.clone(),
+                        format!(
+                            "Index {} is out of bounds for a {} of length {}",
+                            index,
+                            values.type_name(),
+                            length
+                        ),
+                    )
+                })
+        }
+    }
+}
+
+/// Resolves an index, counting negative ones back from `length`.
+fn position(index: &Object, length: i64, span: &Span) -> Result<i64, QclError> {
+    match index {
+        Object::Int(index) if *index < 0 => Ok(length + index),
+        Object::Int(index) => Ok(*index),
+        index => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Expected an int index but found {}", index.type_name()),
+        )),
+    }
+}
@@ -2,53 +2,73 @@ use crate::interpreter::Interpreter;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
 use crate::qcl_error::QclError;
+use crate::rates::Rates;
 use env_logger::Env;
-use std::fs;
+use std::{env, fs, process};
 
+mod arithmetic;
 mod ast;
+mod bigint;
+mod builtins;
+mod complex;
+mod datetime;
+mod decimal;
 mod environment;
+mod format;
 mod interpreter;
 mod lexer;
+mod list;
+mod map;
 mod object;
 mod parser;
+mod percent;
 mod qcl_error;
+mod rates;
+mod rational;
+mod settings;
 mod span;
 mod token;
+mod units;
 
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("error")).init();
 
-    let source = fs::read_to_string("example.qcl").expect("Unable to read file!");
+    let mut path = "example.qcl".to_string();
+    let mut rates = Rates::new();
+    let mut arguments = env::args().skip(1);
+    while let Some(argument) = arguments.next() {
+        if argument != "--rates" {
+            path = argument;
+            continue;
+        }
+        let Some(rates_path) = arguments.next() else {
+            eprintln!("Expected a rate table path after --rates");
+            process::exit(1);
+        };
+        rates = Rates::load(&rates_path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            process::exit(1);
+        });
+    }
+
+    let source = fs::read_to_string(path).expect("Unable to read file!");
     println!("Source: \"\"\"{}\"\"\"", source);
 
-    match interpret(source) {
+    match interpret(source, rates) {
         Ok(()) => (),
         Err(error) => println!("{}", error),
     }
 }
 
-fn interpret(source: String) -> Result<(), QclError> {
+fn interpret(source: String, rates: Rates) -> Result<(), QclError> {
     println!("Lexing:");
-    let lexer_result = Lexer::new(source.clone()).lex();
-    let tokens = match lexer_result {
-        Ok(tokens) => tokens,
-        Err(error) => return Err(error),
-    };
+    let tokens = Lexer::new(source.clone()).lex()?;
     println!("{:?}", tokens);
 
     println!("\nParsing:");
-    let parser_result = Parser::new(source, tokens).parse();
-    let ast = match parser_result {
-        Ok(ast) => ast,
-        Err(error) => return Err(error),
-    };
+    let ast = Parser::new(source, tokens).parse()?;
     println!("{:?}", ast);
 
     println!("\nInterpreting:");
-    let interpreter_result = Interpreter::new(ast).interpret();
-    match interpreter_result {
-        Ok(()) => (),
-        Err(error) => return Err(error),
-    };
-    Ok(())
+    Interpreter::new(ast, rates).interpret()
 }
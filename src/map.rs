@@ -0,0 +1,33 @@
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::span::Span;
+use std::rc::Rc;
+
+pub fn map(entries: Vec<(String, Object)>) -> Object {
+    Object::Map(Rc::new(entries))
+}
+
+/// Evaluates `value.name`.
+pub fn field(value: &Object, name: &str, span: &Span) -> Result<Object, QclError> {
+    let Object::Map(entries) = value else {
+        return Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot access field {} of {}", name, value.type_name()),
+        ));
+    };
+    get(entries, name).cloned().ok_or_else(|| {
+        QclError::new(
+            QclErrorType::KeyError,
+            span.clone(),
+            format!("Map has no key {}", name),
+        )
+    })
+}
+
+pub fn get<'a>(entries: &'a [(String, Object)], name: &str) -> Option<&'a Object> {
+    entries
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value)
+}
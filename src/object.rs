@@ -1,4 +1,289 @@
-#[derive(Debug)]
+use crate::arithmetic;
+use crate::ast::Statement;
+use crate::bigint::BigInt;
+use crate::builtins::Builtin;
+use crate::complex::Complex;
+use crate::datetime::{self, Date, DateTime, Time};
+use crate::decimal::Decimal;
+use crate::environment::Environment;
+use crate::map;
+use crate::qcl_error::QclError;
+use crate::rational::Rational;
+use crate::span::{Span, Spanned};
+use crate::units::Quantity;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
 pub enum Object {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    BigInt(Rc<BigInt>),
+    Rational(Rc<Rational>),
+    Decimal(Rc<Decimal>),
     Float(f64),
+    Complex(Complex),
+    Quantity(Rc<Quantity>),
+    Percentage(Box<Object>),
+    Date(Date),
+    Time(Time),
+    DateTime(DateTime),
+    Str(Rc<str>),
+    List(Rc<RefCell<Vec<Object>>>),
+    Map(Rc<Vec<(String, Object)>>),
+    Tuple(Rc<Vec<Object>>),
+    Range(Range),
+    Function(Rc<Function>),
+    Builtin(&'static Builtin),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub start: Box<Object>,
+    pub end: Box<Object>,
+    pub step: Box<Object>,
+    pub inclusive: bool,
+}
+
+pub struct Function {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: Rc<Spanned<Statement>>,
+    pub environment: Rc<RefCell<Environment>>,
+}
+
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Nil => "nil",
+            Object::Bool(_) => "bool",
+            Object::Int(_) | Object::BigInt(_) => "int",
+            Object::Rational(_) => "rational",
+            Object::Decimal(_) => "decimal",
+            Object::Float(_) => "float",
+            Object::Complex(_) => "complex",
+            Object::Quantity(_) => "quantity",
+            Object::Percentage(_) => "percentage",
+            Object::Date(_) => "date",
+            Object::Time(_) => "time",
+            Object::DateTime(_) => "datetime",
+            Object::Str(_) => "string",
+            Object::List(_) => "list",
+            Object::Map(_) => "map",
+            Object::Tuple(_) => "tuple",
+            Object::Range(_) => "range",
+            Object::Function(_) | Object::Builtin(_) => "function",
+        }
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Object) -> bool {
+        match (self, other) {
+            (Object::Nil, Object::Nil) => true,
+            (Object::Bool(left), Object::Bool(right)) => left == right,
+            (left, right) if arithmetic::is_number(left) && arithmetic::is_number(right) => {
+                arithmetic::compare(left, right) == Some(Ordering::Equal)
+            }
+            (Object::Quantity(_), Object::Quantity(_))
+            | (Object::Percentage(_), Object::Percentage(_)) => {
+                arithmetic::compare(self, other) == Some(Ordering::Equal)
+            }
+            (left, right) if datetime::is_datetime(left) && datetime::is_datetime(right) => {
+                arithmetic::compare(self, other) == Some(Ordering::Equal)
+            }
+            (Object::Str(left), Object::Str(right)) => left == right,
+            (Object::List(left), Object::List(right)) => *left.borrow() == *right.borrow(),
+            (Object::Tuple(left), Object::Tuple(right)) => left == right,
+            (Object::Map(left), Object::Map(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .all(|(key, value)| map::get(right, key) == Some(value))
+            }
+            (Object::Range(left), Object::Range(right)) => left == right,
+            (Object::Function(left), Object::Function(right)) => Rc::ptr_eq(left, right),
+            (Object::Builtin(left), Object::Builtin(right)) => std::ptr::eq(*left, *right),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Object::Nil => write!(f, "nil"),
+            Object::Bool(boolean) => write!(f, "{}", boolean),
+            Object::Int(number) => write!(f, "{}", number),
+            Object::BigInt(number) => write!(f, "{}", number),
+            Object::Rational(number) => write!(f, "{}", number),
+            Object::Decimal(number) => write!(f, "{}", number),
+            Object::Float(number) => write!(f, "{}", number),
+            Object::Complex(number) => write!(f, "{}", number),
+            Object::Quantity(quantity) => write!(f, "{}", quantity),
+            Object::Percentage(percent) => write!(f, "{}%", percent),
+            Object::Date(date) => write!(f, "{}", date),
+            Object::Time(time) => write!(f, "{}", time),
+            Object::DateTime(datetime) => write!(f, "{}", datetime),
+            Object::Str(string) => write!(f, "{}", string),
+            Object::List(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.borrow().iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, value)?;
+                }
+                write!(f, "]")
+            }
+            Object::Tuple(values) => {
+                write!(f, "(")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_element(f, value)?;
+                }
+                if values.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Object::Map(entries) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if key.chars().all(|ch| ch.is_alphanumeric() || ch == '_') {
+                        write!(f, "{}: ", key)?;
+                    } else {
+                        write!(f, "\"{}\": ", key)?;
+                    }
+                    write_element(f, value)?;
+                }
+                write!(f, "}}")
+            }
+            Object::Range(range) => {
+                let dots = if range.inclusive { "..=" } else { ".." };
+                write!(f, "{}{}{}", range.start, dots, range.end)?;
+                if *range.step != Object::Int(1) {
+                    write!(f, " step {}", range.step)?;
+                }
+                Ok(())
+            }
+            Object::Function(function) => write!(f, "{:?}", function),
+            Object::Builtin(builtin) => write!(f, "{:?}", builtin),
+        }
+    }
+}
+
+/// Writes a value inside a list or map, quoting strings.
+fn write_element(f: &mut fmt::Formatter, value: &Object) -> fmt::Result {
+    match value {
+        Object::Str(string) => write!(f, "\"{}\"", string),
+        value => write!(f, "{}", value),
+    }
+}
+
+impl Range {
+    /// Computes the element at `index` as `start + index * step` so that
+    /// iterating does not accumulate rounding errors, or returns None past the
+    /// end. A later float element that differs from the end only by rounding,
+    /// relative to the size of the bounds, counts as the end.
+    pub fn get(&self, index: usize, span: &Span) -> Result<Option<Object>, QclError> {
+        let offset = arithmetic::multiply(Object::Int(index as i64), (*self.step).clone(), span)?;
+        let value = arithmetic::add((*self.start).clone(), offset, span)?;
+        let bounds = arithmetic::to_float(&self.start).zip(arithmetic::to_float(&self.end));
+        let end = match (&value, bounds) {
+            (Object::Float(number), Some((start, end))) if index > 0 => {
+                let tolerance = start.abs().max(end.abs()) * 1e-12;
+                ((number - end).abs() <= tolerance).then_some(end)
+            }
+            _ => None,
+        };
+        let ordering = match end {
+            Some(_) => Some(Ordering::Equal),
+            None => arithmetic::compare(&value, &self.end),
+        };
+        let descending = arithmetic::compare(&self.step, &Object::Int(0)) == Some(Ordering::Less);
+        match ordering.map(|ordering| {
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }) {
+            Some(Ordering::Less) => Ok(Some(value)),
+            Some(Ordering::Equal) if self.inclusive => Ok(Some(end.map_or(value, Object::Float))),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<fn {}({})>", self.name, self.parameters.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elements(start: Object, end: Object, step: Object, inclusive: bool) -> Vec<Object> {
+        let range = Range {
+            start: Box::new(start),
+            end: Box::new(end),
+            step: Box::new(step),
+            inclusive,
+        };
+        let span = Span::new(Rc::new(String::new()), 0, 0);
+        (0..)
+            .map_while(|index| range.get(index, &span).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_elements_close_to_the_end() {
+        let values = elements(
+            Object::Float(0.0),
+            Object::Float(1e-10),
+            Object::Float(1.0),
+            false,
+        );
+        assert_eq!(values, vec![Object::Float(0.0)]);
+        let values = elements(
+            Object::Float(0.0),
+            Object::Float(1e-10),
+            Object::Float(1.0),
+            true,
+        );
+        assert_eq!(values, vec![Object::Float(0.0)]);
+        let values = elements(
+            Object::Float(1.5),
+            Object::Float(1.5000000001),
+            Object::Float(1.0),
+            true,
+        );
+        assert_eq!(values, vec![Object::Float(1.5)]);
+    }
+
+    #[test]
+    fn reaches_the_end_despite_rounding() {
+        let values = elements(Object::Int(0), Object::Int(1), Object::Float(0.1), true);
+        assert_eq!(values.len(), 11);
+        assert!(matches!(values[10], Object::Float(end) if end == 1.0));
+    }
+
+    #[test]
+    fn leaves_elements_unrounded() {
+        let third = Object::Float(1.0 / 3.0);
+        let values = elements(Object::Int(0), Object::Int(1), third.clone(), false);
+        assert!(matches!(values[1], Object::Float(value) if value == 1.0 / 3.0));
+        assert_eq!(values.len(), 3);
+    }
 }
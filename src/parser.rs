@@ -1,7 +1,12 @@
-use crate::ast::{Expression, Statement};
+use crate::ast::{Expression, MatchArm, Pattern, Statement, StringSegment};
+use crate::bigint::BigInt;
+use crate::datetime::{Date, DateTime, Time};
+use crate::decimal::Decimal;
+use crate::lexer::Lexer;
 use crate::qcl_error::{QclError, QclErrorType};
 use crate::span::{Span, Spanned};
-use crate::token::Token;
+use crate::token::{StringPart, Token};
+use crate::units::Unit;
 use log::trace;
 use std::rc::Rc;
 
@@ -9,14 +14,22 @@ pub struct Parser {
     source: Rc<String>,
     tokens: Vec<Spanned<Token>>,
     index: usize,
+    function_depth: usize,
+    loop_depth: usize,
 }
 
 impl Parser {
     pub fn new(source: String, tokens: Vec<Spanned<Token>>) -> Self {
+        Parser::with_source(Rc::new(source), tokens)
+    }
+
+    fn with_source(source: Rc<String>, tokens: Vec<Spanned<Token>>) -> Self {
         Parser {
-            source: Rc::new(source),
+            source,
             tokens,
             index: 0,
+            function_depth: 0,
+            loop_depth: 0,
         }
     }
 
@@ -30,8 +43,27 @@ impl Parser {
     fn parse_statement(&mut self) -> Result<Spanned<Statement>, QclError> {
         trace!("Parsing statement");
         let statement = match self.peek().item {
-            Token::LeftCurly => self.parse_block(),
+            Token::LeftCurly if !self.is_map_literal() => self.parse_block(),
             Token::Print => self.parse_print(),
+            Token::Let => self.parse_let(),
+            Token::Fn => self.parse_function(),
+            Token::Return => self.parse_return(),
+            Token::If => self.parse_if(),
+            Token::While => self.parse_while(),
+            Token::For => self.parse_for(),
+            Token::Break | Token::Continue => self.parse_loop_control(),
+            Token::Set => self.parse_set(),
+            Token::Identifier(ref name)
+                if name == "rates" && matches!(self.peek_next().item, Token::String(_)) =>
+            {
+                self.parse_rates()
+            }
+            Token::Identifier(_) if self.peek_next().item == Token::Equal => {
+                self.parse_assignment()
+            }
+            Token::LeftParen | Token::LeftBracket if self.is_destructuring() => {
+                self.parse_assignment()
+            }
             _ => {
                 let expression = self.parse_expression()?;
                 let span = Span::new(
@@ -77,9 +109,465 @@ impl Parser {
         Ok(Spanned::new(Statement::Print(Box::new(inner)), span))
     }
 
+    fn parse_let(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing let");
+        let let_token = self.advance_specific(Token::Let)?;
+        let pattern = self.parse_pattern()?;
+        self.advance_specific(Token::Equal)?;
+        let value = self.parse_expression()?;
+        let span = Span::new(self.source.clone(), let_token.span.start, value.span.end);
+        Ok(Spanned::new(Statement::Let(pattern, Box::new(value)), span))
+    }
+
+    fn parse_assignment(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing assignment");
+        let start = self.peek().span.start;
+        let pattern = self.parse_pattern()?;
+        self.advance_specific(Token::Equal)?;
+        let value = self.parse_expression()?;
+        let span = Span::new(self.source.clone(), start, value.span.end);
+        Ok(Spanned::new(
+            Statement::Assignment(pattern, Box::new(value)),
+            span,
+        ))
+    }
+
+    /// Parses a pattern: `_`, a name, a value or range of values, a tuple or
+    /// list of patterns such as `(a, b)` or `[first, ..rest]`, or a record
+    /// such as `{name: "pump", flow}`.
+    fn parse_pattern(&mut self) -> Result<Pattern, QclError> {
+        trace!("Parsing pattern");
+        let open = self.peek();
+        let close = match open.item {
+            Token::Identifier(name) if name == "_" => {
+                self.advance();
+                return Ok(Pattern::Wildcard);
+            }
+            Token::Identifier(_) => return Ok(Pattern::Name(self.advance_identifier()?)),
+            Token::LeftParen => Token::RightParen,
+            Token::LeftBracket => Token::RightBracket,
+            Token::LeftCurly => return self.parse_record_pattern(),
+            _ => {
+                let value = self.parse_conversion()?;
+                let inclusive = match self.peek().item {
+                    Token::DotDot => false,
+                    Token::DotDotEqual => true,
+                    _ => return Ok(Pattern::Value(Box::new(value))),
+                };
+                self.advance();
+                let end = self.parse_conversion()?;
+                return Ok(Pattern::Range(Box::new(value), Box::new(end), inclusive));
+            }
+        };
+        self.advance();
+        let mut patterns = Vec::new();
+        let mut commas = 0;
+        while self.peek().item != close {
+            if self.peek().item == Token::DotDot {
+                let dots = self.advance_specific(Token::DotDot)?;
+                if patterns
+                    .iter()
+                    .any(|pattern| matches!(pattern, Pattern::Rest(_)))
+                {
+                    return Err(QclError::new(
+                        QclErrorType::SyntaxError,
+                        dots.span,
+                        "Expected at most one ..rest in a pattern".to_string(),
+                    ));
+                }
+                patterns.push(Pattern::Rest(self.advance_identifier()?));
+            } else {
+                patterns.push(self.parse_pattern()?);
+            }
+            if self.peek().item != close {
+                self.advance_specific(Token::Comma)?;
+                commas += 1;
+            }
+        }
+        self.advance_specific(close)?;
+        match open.item {
+            Token::LeftParen if commas == 0 && matches!(patterns[..], [Pattern::Name(_)]) => {
+                Ok(patterns.remove(0))
+            }
+            Token::LeftParen => Ok(Pattern::Tuple(patterns)),
+            _ => Ok(Pattern::List(patterns)),
+        }
+    }
+
+    /// Parses `{key: pattern, ...}`, where a lone `key` binds the field to a
+    /// name of its own.
+    fn parse_record_pattern(&mut self) -> Result<Pattern, QclError> {
+        trace!("Parsing record pattern");
+        self.advance_specific(Token::LeftCurly)?;
+        let mut fields = Vec::new();
+        self.skip_newlines();
+        while self.peek().item != Token::RightCurly {
+            let key_token = self.peek();
+            let key = match key_token.item {
+                Token::Identifier(ref key) | Token::String(ref key) => key.clone(),
+                _ => {
+                    return Err(QclError::new(
+                        QclErrorType::SyntaxError,
+                        key_token.span,
+                        format!("Expected a map key but found {}", key_token.item),
+                    ))
+                }
+            };
+            self.advance();
+            let pattern = match key_token.item {
+                Token::Identifier(_) if self.peek().item != Token::Colon => {
+                    Pattern::Name(key.clone())
+                }
+                _ => {
+                    self.advance_specific(Token::Colon)?;
+                    self.parse_pattern()?
+                }
+            };
+            fields.push((key, pattern));
+            self.skip_newlines();
+            if self.peek().item != Token::RightCurly {
+                self.advance_specific(Token::Comma)?;
+                self.skip_newlines();
+            }
+        }
+        self.advance_specific(Token::RightCurly)?;
+        Ok(Pattern::Record(fields))
+    }
+
+    /// Checks whether a statement starting with a bracket is a pattern being
+    /// assigned to, as in `(a, b) = (b, a)`.
+    fn is_destructuring(&self) -> bool {
+        let mut depth = 0;
+        for token in &self.tokens[self.index..] {
+            match token.item {
+                Token::LeftParen | Token::LeftBracket => depth += 1,
+                Token::RightParen | Token::RightBracket => depth -= 1,
+                Token::Newline | Token::End => return false,
+                Token::Equal if depth == 0 => return true,
+                _ if depth == 0 => return false,
+                _ => (),
+            }
+        }
+        false
+    }
+
+    fn parse_function(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing function");
+        let fn_token = self.advance_specific(Token::Fn)?;
+        let name = self.advance_identifier()?;
+        let parameters = self.parse_parameters()?;
+        self.function_depth += 1;
+        let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        let body = match self.peek().item {
+            Token::Equal => {
+                self.advance();
+                let value = self.parse_expression()?;
+                let span = value.span.clone();
+                Ok(Spanned::new(Statement::Return(Box::new(value)), span))
+            }
+            _ => self.parse_block(),
+        };
+        self.function_depth -= 1;
+        self.loop_depth = loop_depth;
+        let body = body?;
+        let span = Span::new(self.source.clone(), fn_token.span.start, body.span.end);
+        Ok(Spanned::new(
+            Statement::Function(name, parameters, Rc::new(body)),
+            span,
+        ))
+    }
+
+    fn parse_parameters(&mut self) -> Result<Vec<String>, QclError> {
+        trace!("Parsing parameters");
+        self.advance_specific(Token::LeftParen)?;
+        let mut parameters = Vec::new();
+        while self.peek().item != Token::RightParen {
+            parameters.push(self.advance_identifier()?);
+            if self.peek().item != Token::RightParen {
+                self.advance_specific(Token::Comma)?;
+            }
+        }
+        self.advance_specific(Token::RightParen)?;
+        Ok(parameters)
+    }
+
+    fn parse_return(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing return");
+        let return_token = self.advance_specific(Token::Return)?;
+        if self.function_depth == 0 {
+            return Err(QclError::new(
+                QclErrorType::SyntaxError,
+                return_token.span,
+                "Cannot return outside of a function".to_string(),
+            ));
+        }
+        let value = self.parse_expression()?;
+        let span = Span::new(self.source.clone(), return_token.span.start, value.span.end);
+        Ok(Spanned::new(Statement::Return(Box::new(value)), span))
+    }
+
+    fn parse_if(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing if");
+        let if_token = self.advance_specific(Token::If)?;
+        let condition = self.parse_expression()?;
+        if self.peek().item == Token::Then {
+            let expression = self.parse_if_expression_branches(if_token.span.start, condition)?;
+            let span = expression.span.clone();
+            return Ok(Spanned::new(
+                Statement::Expression(Box::new(expression)),
+                span,
+            ));
+        }
+        let then_branch = self.parse_block()?;
+        let else_branch = match self.peek().item {
+            Token::Else => {
+                self.advance();
+                match self.peek().item {
+                    Token::If => Some(Box::new(self.parse_if()?)),
+                    _ => Some(Box::new(self.parse_block()?)),
+                }
+            }
+            _ => None,
+        };
+        let end = match &else_branch {
+            Some(else_branch) => else_branch.span.end,
+            None => then_branch.span.end,
+        };
+        let span = Span::new(self.source.clone(), if_token.span.start, end);
+        Ok(Spanned::new(
+            Statement::If(Box::new(condition), Box::new(then_branch), else_branch),
+            span,
+        ))
+    }
+
+    fn parse_if_expression_branches(
+        &mut self,
+        start: usize,
+        condition: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing if expression");
+        self.advance_specific(Token::Then)?;
+        let then_branch = self.parse_expression()?;
+        self.advance_specific(Token::Else)?;
+        let else_branch = self.parse_expression()?;
+        let span = Span::new(self.source.clone(), start, else_branch.span.end);
+        Ok(Spanned::new(
+            Expression::If(
+                Box::new(condition),
+                Box::new(then_branch),
+                Box::new(else_branch),
+            ),
+            span,
+        ))
+    }
+
+    fn parse_while(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing while");
+        let while_token = self.advance_specific(Token::While)?;
+        let condition = self.parse_expression()?;
+        let body = self.parse_loop_body()?;
+        let span = Span::new(self.source.clone(), while_token.span.start, body.span.end);
+        Ok(Spanned::new(
+            Statement::While(Box::new(condition), Box::new(body)),
+            span,
+        ))
+    }
+
+    fn parse_for(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing for");
+        let for_token = self.advance_specific(Token::For)?;
+        let name = self.advance_identifier()?;
+        self.advance_specific(Token::In)?;
+        let iterable = self.parse_expression()?;
+        let body = self.parse_loop_body()?;
+        let span = Span::new(self.source.clone(), for_token.span.start, body.span.end);
+        Ok(Spanned::new(
+            Statement::For(name, Box::new(iterable), Box::new(body)),
+            span,
+        ))
+    }
+
+    fn parse_loop_body(&mut self) -> Result<Spanned<Statement>, QclError> {
+        self.loop_depth += 1;
+        let body = self.parse_block();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn parse_loop_control(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing loop control");
+        let token = self.peek();
+        self.advance();
+        if self.loop_depth == 0 {
+            return Err(QclError::new(
+                QclErrorType::SyntaxError,
+                token.span,
+                format!("Cannot use {} outside of a loop", token.item),
+            ));
+        }
+        let statement = match token.item {
+            Token::Break => Statement::Break,
+            _ => Statement::Continue,
+        };
+        Ok(Spanned::new(statement, token.span))
+    }
+
+    fn parse_set(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing set");
+        let set_token = self.advance_specific(Token::Set)?;
+        let name = self.advance_identifier()?;
+        self.advance_specific(Token::Equal)?;
+        let value = self.peek();
+        let string = match value.item {
+            Token::Identifier(string) | Token::Number(string) => string,
+            _ => {
+                return Err(QclError::new(
+                    QclErrorType::SyntaxError,
+                    value.span,
+                    format!("Expected a setting value but found {}", value.item),
+                ))
+            }
+        };
+        self.advance();
+        let span = Span::new(self.source.clone(), set_token.span.start, value.span.end);
+        Ok(Spanned::new(Statement::Set(name, string), span))
+    }
+
+    fn parse_rates(&mut self) -> Result<Spanned<Statement>, QclError> {
+        trace!("Parsing rates");
+        let rates_token = self.peek();
+        self.advance();
+        let path = self.peek();
+        let Token::String(string) = path.item else {
+            return Err(QclError::new(
+                QclErrorType::SyntaxError,
+                path.span,
+                format!("Expected a rate table path but found {}", path.item),
+            ));
+        };
+        self.advance();
+        let span = Span::new(self.source.clone(), rates_token.span.start, path.span.end);
+        Ok(Spanned::new(Statement::Rates(string), span))
+    }
+
     fn parse_expression(&mut self) -> Result<Spanned<Expression>, QclError> {
         trace!("Parsing expression");
-        self.parse_addition()
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing or");
+        let mut current = self.parse_and()?;
+        while self.peek().item == Token::Or {
+            self.advance();
+            let right = self.parse_and()?;
+            let span = Span::new(self.source.clone(), current.span.start, right.span.end);
+            current = Spanned::new(Expression::Or(Box::new(current), Box::new(right)), span);
+        }
+        Ok(current)
+    }
+
+    fn parse_and(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing and");
+        let mut current = self.parse_not()?;
+        while self.peek().item == Token::And {
+            self.advance();
+            let right = self.parse_not()?;
+            let span = Span::new(self.source.clone(), current.span.start, right.span.end);
+            current = Spanned::new(Expression::And(Box::new(current), Box::new(right)), span);
+        }
+        Ok(current)
+    }
+
+    fn parse_not(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing not");
+        let current = self.peek();
+        match current.item {
+            Token::Not => {
+                self.advance();
+                let next = Box::new(self.parse_not()?);
+                let span = Span::new(self.source.clone(), current.span.start, next.span.end);
+                Ok(Spanned::new(Expression::Not(next), span))
+            }
+            _ => self.parse_comparison(),
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing comparison");
+        let left = self.parse_range()?;
+        let comparison: fn(_, _) -> Expression = match self.peek().item {
+            Token::EqualEqual => Expression::Equal,
+            Token::BangEqual => Expression::NotEqual,
+            Token::Less => Expression::Less,
+            Token::LessEqual => Expression::LessEqual,
+            Token::Greater => Expression::Greater,
+            Token::GreaterEqual => Expression::GreaterEqual,
+            _ => return Ok(left),
+        };
+        self.advance();
+        let right = self.parse_range()?;
+        let span = Span::new(self.source.clone(), left.span.start, right.span.end);
+        Ok(Spanned::new(
+            comparison(Box::new(left), Box::new(right)),
+            span,
+        ))
+    }
+
+    fn parse_range(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing range");
+        let start = self.parse_conversion()?;
+        let inclusive = match self.peek().item {
+            Token::DotDot => false,
+            Token::DotDotEqual => true,
+            _ => return Ok(start),
+        };
+        self.advance();
+        let end = self.parse_conversion()?;
+        let step = if self.peek_is_word("step") {
+            self.advance();
+            Some(Box::new(self.parse_conversion()?))
+        } else {
+            None
+        };
+        let span_end = step.as_ref().map_or(end.span.end, |step| step.span.end);
+        let span = Span::new(self.source.clone(), start.span.start, span_end);
+        Ok(Spanned::new(
+            Expression::Range(Box::new(start), Box::new(end), step, inclusive),
+            span,
+        ))
+    }
+
+    fn parse_conversion(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing conversion");
+        let mut value = self.parse_addition()?;
+        loop {
+            if self.is_conversion() {
+                self.advance();
+                let unit = self.parse_unit()?;
+                let span = Span::new(self.source.clone(), value.span.start, unit.span.end);
+                value = Spanned::new(Expression::Conversion(Box::new(value), unit.item), span);
+            } else if self.peek().item == Token::As {
+                self.advance();
+                self.advance_specific(Token::Percent)?;
+                self.advance_specific(Token::Identifier("of".to_string()))?;
+                let whole = self.parse_addition()?;
+                let span = Span::new(self.source.clone(), value.span.start, whole.span.end);
+                value = Spanned::new(
+                    Expression::AsPercentOf(Box::new(value), Box::new(whole)),
+                    span,
+                );
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Checks for `to` or `in` followed by a unit, so that `in` still ends
+    /// the iterable of a for loop.
+    fn is_conversion(&self) -> bool {
+        matches!(self.peek().item, Token::To | Token::In)
+            && matches!(self.peek_next().item, Token::Identifier(name) if Unit::is_unit(&name))
     }
 
     fn parse_addition(&mut self) -> Result<Spanned<Expression>, QclError> {
@@ -134,6 +622,31 @@ impl Parser {
                         span,
                     );
                 }
+                Token::DoubleSlash => {
+                    self.advance();
+                    let right = self.parse_unary()?;
+                    let span = Span::new(self.source.clone(), current.span.start, right.span.end);
+                    current = Spanned::new(
+                        Expression::FloorDivision(Box::new(current), Box::new(right)),
+                        span,
+                    );
+                }
+                Token::Percent => {
+                    self.advance();
+                    let right = self.parse_unary()?;
+                    let span = Span::new(self.source.clone(), current.span.start, right.span.end);
+                    current =
+                        Spanned::new(Expression::Modulo(Box::new(current), Box::new(right)), span);
+                }
+                Token::Identifier(ref name) if name == "of" => {
+                    self.advance();
+                    let right = self.parse_unary()?;
+                    let span = Span::new(self.source.clone(), current.span.start, right.span.end);
+                    current = Spanned::new(
+                        Expression::PercentOf(Box::new(current), Box::new(right)),
+                        span,
+                    );
+                }
                 _ => return Ok(current),
             }
         }
@@ -143,26 +656,439 @@ impl Parser {
         trace!("Parsing unary");
         let current = self.peek();
         match current.item {
+            Token::Minus => {
+                self.advance();
+                let next = Box::new(self.parse_unary()?);
+                let span = Span::new(self.source.clone(), current.span.start, next.span.end);
+                Ok(Spanned::new(Expression::Negation(next), span))
+            }
+            _ => self.parse_power(),
+        }
+    }
+
+    fn parse_power(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing power");
+        let mut base = self.parse_primary()?;
+        if self.is_unit_suffix(&base) {
+            base = self.parse_quantity(base)?;
+            // Quantities written side by side, as in `2h 15min`, are summed.
+            while matches!(self.peek().item, Token::Number(_))
+                && matches!(self.peek_next().item, Token::Identifier(name) if Unit::is_unit(&name))
+            {
+                let value = self.parse_primary()?;
+                let part = self.parse_quantity(value)?;
+                let span = Span::new(self.source.clone(), base.span.start, part.span.end);
+                base = Spanned::new(Expression::Addition(Box::new(base), Box::new(part)), span);
+            }
+        }
+        loop {
+            base = match self.peek().item {
+                Token::LeftParen => self.parse_call(base)?,
+                Token::LeftBracket => self.parse_index(base)?,
+                Token::Dot => self.parse_field(base)?,
+                _ => break,
+            };
+        }
+        if self.peek().item == Token::Percentage {
+            let percent = self.advance_specific(Token::Percentage)?;
+            let span = Span::new(self.source.clone(), base.span.start, percent.span.end);
+            base = Spanned::new(Expression::Percentage(Box::new(base)), span);
+        }
+        if self.peek().item != Token::Caret {
+            return Ok(base);
+        }
+        self.advance();
+        let exponent = self.parse_unary()?;
+        let span = Span::new(self.source.clone(), base.span.start, exponent.span.end);
+        Ok(Spanned::new(
+            Expression::Power(Box::new(base), Box::new(exponent)),
+            span,
+        ))
+    }
+
+    fn is_unit_suffix(&self, value: &Spanned<Expression>) -> bool {
+        let is_literal = matches!(
+            value.item,
+            Expression::Integer(_)
+                | Expression::BigInteger(_)
+                | Expression::Float(_)
+                | Expression::Decimal(_)
+        );
+        is_literal && matches!(self.peek().item, Token::Identifier(name) if Unit::is_unit(&name))
+    }
+
+    fn parse_quantity(
+        &mut self,
+        value: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing quantity");
+        let unit = self.parse_unit()?;
+        let span = Span::new(self.source.clone(), value.span.start, unit.span.end);
+        Ok(Spanned::new(
+            Expression::Quantity(Box::new(value), unit.item),
+            span,
+        ))
+    }
+
+    fn parse_unit(&mut self) -> Result<Spanned<Vec<(String, i32)>>, QclError> {
+        trace!("Parsing unit");
+        let start = self.peek().span.start;
+        let mut terms = Vec::new();
+        let mut sign = 1;
+        loop {
+            let mut end = self.peek().span.end;
+            let name = self.advance_identifier()?;
+            let mut exponent = 1;
+            if self.peek().item == Token::Caret {
+                self.advance();
+                let negative = self.peek().item == Token::Minus;
+                if negative {
+                    self.advance();
+                }
+                let power = self.peek();
+                exponent = match &power.item {
+                    Token::Number(string) => string.parse().ok(),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    QclError::new(
+                        QclErrorType::SyntaxError,
+                        power.span.clone(),
+                        format!("Expected an integer unit power but found {}", power.item),
+                    )
+                })?;
+                if negative {
+                    exponent = -exponent;
+                }
+                end = power.span.end;
+                self.advance();
+            }
+            terms.push((name, sign * exponent));
+            sign = match (self.peek().item, self.peek_next().item) {
+                (Token::Star, Token::Identifier(name)) if Unit::is_unit(&name) => 1,
+                (Token::Slash, Token::Identifier(name)) if Unit::is_unit(&name) => -1,
+                _ => {
+                    let span = Span::new(self.source.clone(), start, end);
+                    return Ok(Spanned::new(terms, span));
+                }
+            };
+            self.advance();
+        }
+    }
+
+    fn parse_call(&mut self, callee: Spanned<Expression>) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing call");
+        let binds_index = matches!(self.peek_next().item, Token::Identifier(_))
+            && self.tokens.get(self.index + 2).map(|token| &token.item) == Some(&Token::Equal);
+        match &callee.item {
+            Expression::Name(name) if binds_index && (name == "sum" || name == "prod") => {
+                return self.parse_series(callee)
+            }
+            _ => (),
+        }
+        self.advance_specific(Token::LeftParen)?;
+        let mut arguments = Vec::new();
+        while self.peek().item != Token::RightParen {
+            arguments.push(self.parse_expression()?);
+            if self.peek().item != Token::RightParen {
+                self.advance_specific(Token::Comma)?;
+            }
+        }
+        let right_paren = self.advance_specific(Token::RightParen)?;
+        let span = Span::new(self.source.clone(), callee.span.start, right_paren.span.end);
+        Ok(Spanned::new(
+            Expression::Call(Box::new(callee), arguments),
+            span,
+        ))
+    }
+
+    /// Parses `sum(k = values, term)` or `prod(k = values, term)`, which bind
+    /// `k` to each of the values in turn.
+    fn parse_series(
+        &mut self,
+        callee: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing series");
+        self.advance_specific(Token::LeftParen)?;
+        let name = self.advance_identifier()?;
+        self.advance_specific(Token::Equal)?;
+        let values = Box::new(self.parse_expression()?);
+        self.advance_specific(Token::Comma)?;
+        let term = Box::new(self.parse_expression()?);
+        let right_paren = self.advance_specific(Token::RightParen)?;
+        let span = Span::new(self.source.clone(), callee.span.start, right_paren.span.end);
+        let series = match callee.item {
+            Expression::Name(operator) if operator == "prod" => {
+                Expression::Product(name, values, term)
+            }
+            _ => Expression::Sum(name, values, term),
+        };
+        Ok(Spanned::new(series, span))
+    }
+
+    fn parse_index(
+        &mut self,
+        values: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing index");
+        self.advance_specific(Token::LeftBracket)?;
+        let index = self.parse_expression()?;
+        let right_bracket = self.advance_specific(Token::RightBracket)?;
+        let span = Span::new(
+            self.source.clone(),
+            values.span.start,
+            right_bracket.span.end,
+        );
+        Ok(Spanned::new(
+            Expression::Index(Box::new(values), Box::new(index)),
+            span,
+        ))
+    }
+
+    /// Parses `[a, b, c]` or a comprehension `[element for name in values if
+    /// condition]`, where the condition is optional.
+    fn parse_list(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing list");
+        let left_bracket = self.advance_specific(Token::LeftBracket)?;
+        let mut elements = Vec::new();
+        self.skip_newlines();
+        while self.peek().item != Token::RightBracket {
+            elements.push(self.parse_expression()?);
+            if elements.len() == 1 && self.peek().item == Token::For {
+                return self.parse_comprehension(left_bracket.span.start, elements.remove(0));
+            }
+            self.skip_newlines();
+            if self.peek().item != Token::RightBracket {
+                self.advance_specific(Token::Comma)?;
+                self.skip_newlines();
+            }
+        }
+        let right_bracket = self.advance_specific(Token::RightBracket)?;
+        let span = Span::new(
+            self.source.clone(),
+            left_bracket.span.start,
+            right_bracket.span.end,
+        );
+        Ok(Spanned::new(Expression::List(elements), span))
+    }
+
+    /// Parses the rest of `(a, b, c)` after its first element. A trailing
+    /// comma makes a single element a tuple, as in `(a,)`.
+    fn parse_tuple(
+        &mut self,
+        start: usize,
+        first: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing tuple");
+        let mut elements = vec![first];
+        while self.peek().item == Token::Comma {
+            self.advance();
+            if self.peek().item == Token::RightParen {
+                break;
+            }
+            elements.push(self.parse_expression()?);
+        }
+        let right_paren = self.advance_specific(Token::RightParen)?;
+        let span = Span::new(self.source.clone(), start, right_paren.span.end);
+        Ok(Spanned::new(Expression::Tuple(elements), span))
+    }
+
+    /// Parses `{key: value, ...}`, where each key is a name or a string.
+    fn parse_map(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing map");
+        let left_curly = self.advance_specific(Token::LeftCurly)?;
+        let mut entries: Vec<(String, Spanned<Expression>)> = Vec::new();
+        self.skip_newlines();
+        while self.peek().item != Token::RightCurly {
+            let key_token = self.peek();
+            let key = match key_token.item {
+                Token::Identifier(key) | Token::String(key) => key,
+                _ => {
+                    return Err(QclError::new(
+                        QclErrorType::SyntaxError,
+                        key_token.span,
+                        format!("Expected a map key but found {}", key_token.item),
+                    ))
+                }
+            };
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(QclError::new(
+                    QclErrorType::SyntaxError,
+                    key_token.span,
+                    format!("Duplicate map key {}", key),
+                ));
+            }
+            self.advance();
+            self.advance_specific(Token::Colon)?;
+            entries.push((key, self.parse_expression()?));
+            self.skip_newlines();
+            if self.peek().item != Token::RightCurly {
+                self.advance_specific(Token::Comma)?;
+                self.skip_newlines();
+            }
+        }
+        let right_curly = self.advance_specific(Token::RightCurly)?;
+        let span = Span::new(
+            self.source.clone(),
+            left_curly.span.start,
+            right_curly.span.end,
+        );
+        Ok(Spanned::new(Expression::Map(entries), span))
+    }
+
+    /// A `{` at the start of a statement opens a block unless its first
+    /// entry is a key followed by a colon, as in `{flow: 3.2}`.
+    fn is_map_literal(&self) -> bool {
+        let mut tokens = self.tokens[self.index + 1..]
+            .iter()
+            .map(|token| &token.item)
+            .skip_while(|token| **token == Token::Newline);
+        matches!(
+            tokens.next(),
+            Some(Token::Identifier(_)) | Some(Token::String(_))
+        ) && tokens.next() == Some(&Token::Colon)
+    }
+
+    fn parse_field(&mut self, value: Spanned<Expression>) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing field");
+        self.advance_specific(Token::Dot)?;
+        let name_token = self.peek();
+        let name = self.advance_identifier()?;
+        let span = Span::new(self.source.clone(), value.span.start, name_token.span.end);
+        Ok(Spanned::new(Expression::Field(Box::new(value), name), span))
+    }
+
+    fn parse_comprehension(
+        &mut self,
+        start: usize,
+        element: Spanned<Expression>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing comprehension");
+        self.advance_specific(Token::For)?;
+        let name = self.advance_identifier()?;
+        self.advance_specific(Token::In)?;
+        let values = self.parse_expression()?;
+        let condition = if self.peek().item == Token::If {
+            self.advance();
+            Some(Box::new(self.parse_expression()?))
+        } else {
+            None
+        };
+        let right_bracket = self.advance_specific(Token::RightBracket)?;
+        let span = Span::new(self.source.clone(), start, right_bracket.span.end);
+        Ok(Spanned::new(
+            Expression::Comprehension(Box::new(element), name, Box::new(values), condition),
+            span,
+        ))
+    }
+
+    fn parse_primary(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing primary");
+        let current = self.peek();
+        match current.item {
+            Token::Number(string) if string.contains('.') => {
+                self.advance();
+                let number = string.parse().expect("Unable to parse number!");
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(Expression::Float(number), span))
+            }
             Token::Number(string) => {
+                self.advance();
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                match string.parse() {
+                    Ok(number) => Ok(Spanned::new(Expression::Integer(number), span)),
+                    Err(_) => {
+                        let number = BigInt::parse(&string).expect("Unable to parse number!");
+                        Ok(Spanned::new(Expression::BigInteger(number), span))
+                    }
+                }
+            }
+            Token::Date(string) => {
+                self.advance();
+                let date = Date::parse(&string).ok_or_else(|| {
+                    QclError::new(
+                        QclErrorType::SyntaxError,
+                        current.span.clone(),
+                        format!("{} is not a valid date", string),
+                    )
+                })?;
+                let Token::Time(time) = self.peek().item else {
+                    let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                    return Ok(Spanned::new(Expression::Date(date), span));
+                };
+                let time_token = self.advance_specific(Token::Time(time.clone()))?;
+                let time = self.parse_time(&time, &time_token.span)?;
+                let span = Span::new(self.source.clone(), current.span.start, time_token.span.end);
+                Ok(Spanned::new(
+                    Expression::DateTime(DateTime::new(date, time)),
+                    span,
+                ))
+            }
+            Token::Time(string) => {
+                self.advance();
+                let time = self.parse_time(&string, &current.span)?;
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(Expression::Time(time), span))
+            }
+            Token::Imaginary(string) => {
                 self.advance();
                 let number = string.parse().expect("Unable to parse number!");
                 let span = Span::new(self.source.clone(), current.span.start, current.span.end);
-                Ok(Spanned::new(Expression::Number(number), span))
+                Ok(Spanned::new(Expression::Imaginary(number), span))
             }
-            Token::Identifier(string) => {
+            Token::Decimal(string) => {
                 self.advance();
+                let number = Decimal::parse(&string).expect("Unable to parse number!");
                 let span = Span::new(self.source.clone(), current.span.start, current.span.end);
-                Ok(Spanned::new(Expression::Name(string), span))
+                Ok(Spanned::new(Expression::Decimal(number), span))
             }
-            Token::Minus => {
+            Token::String(string) => {
                 self.advance();
-                let next = Box::new(self.parse_unary()?);
-                let span = Span::new(self.source.clone(), current.span.start, next.span.end);
-                Ok(Spanned::new(Expression::Negation(next), span))
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(Expression::String(string), span))
+            }
+            Token::InterpolatedString(parts) => {
+                self.advance();
+                let segments = self.parse_interpolation(parts)?;
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(Expression::Interpolation(segments), span))
+            }
+            Token::True | Token::False => {
+                self.advance();
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(
+                    Expression::Boolean(current.item == Token::True),
+                    span,
+                ))
+            }
+            Token::If => {
+                self.advance();
+                let condition = self.parse_expression()?;
+                self.parse_if_expression_branches(current.span.start, condition)
+            }
+            Token::Identifier(string) if self.peek_next().item == Token::Arrow => {
+                self.advance();
+                self.parse_lambda(current.span.start, vec![string])
+            }
+            Token::LeftParen if self.is_lambda_parameters() => {
+                let parameters = self.parse_parameters()?;
+                self.parse_lambda(current.span.start, parameters)
+            }
+            Token::Identifier(string) => {
+                self.advance();
+                let span = Span::new(self.source.clone(), current.span.start, current.span.end);
+                Ok(Spanned::new(Expression::Name(string), span))
             }
+            Token::LeftBracket => self.parse_list(),
+            Token::LeftCurly => self.parse_map(),
+            Token::Match => self.parse_match(),
             Token::LeftParen => {
                 self.advance();
-                let inner = self.parse_expression()?.item;
+                let inner = self.parse_expression()?;
+                if self.peek().item == Token::Comma {
+                    return self.parse_tuple(current.span.start, inner);
+                }
+                let inner = inner.item;
                 let right_paren = self.advance_specific(Token::RightParen)?;
                 let span = Span::new(
                     self.source.clone(),
@@ -179,6 +1105,106 @@ impl Parser {
         }
     }
 
+    fn parse_interpolation(&self, parts: Vec<StringPart>) -> Result<Vec<StringSegment>, QclError> {
+        trace!("Parsing interpolation");
+        let mut segments = Vec::new();
+        for part in parts {
+            let segment = match part {
+                StringPart::Text(text) => StringSegment::Text(text),
+                StringPart::Value { start, end, format } => {
+                    let tokens = Lexer::with_range(self.source.clone(), start, end).lex()?;
+                    let mut parser = Parser::with_source(self.source.clone(), tokens);
+                    let value = parser.parse_expression()?;
+                    parser.advance_specific(Token::End)?;
+                    StringSegment::Value(value, format)
+                }
+            };
+            segments.push(segment);
+        }
+        Ok(segments)
+    }
+
+    /// Parses `match value { pattern => result, ... }`, where arms are
+    /// separated by commas or newlines and a pattern may be followed by an
+    /// `if` guard.
+    fn parse_match(&mut self) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing match");
+        let match_token = self.advance_specific(Token::Match)?;
+        let value = self.parse_expression()?;
+        self.advance_specific(Token::LeftCurly)?;
+        let mut arms = Vec::new();
+        self.skip_newlines();
+        while self.peek().item != Token::RightCurly {
+            let pattern = self.parse_pattern()?;
+            let guard = if self.peek().item == Token::If {
+                self.advance();
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
+            self.advance_specific(Token::FatArrow)?;
+            let body = self.parse_expression()?;
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            self.skip_newlines();
+            if self.peek().item == Token::Comma {
+                self.advance();
+                self.skip_newlines();
+            }
+        }
+        let right_curly = self.advance_specific(Token::RightCurly)?;
+        let span = Span::new(
+            self.source.clone(),
+            match_token.span.start,
+            right_curly.span.end,
+        );
+        Ok(Spanned::new(Expression::Match(Box::new(value), arms), span))
+    }
+
+    fn parse_time(&self, string: &str, span: &Span) -> Result<Time, QclError> {
+        Time::parse(string).ok_or_else(|| {
+            QclError::new(
+                QclErrorType::SyntaxError,
+                span.clone(),
+                format!("{} is not a valid time", string),
+            )
+        })
+    }
+
+    fn parse_lambda(
+        &mut self,
+        start: usize,
+        parameters: Vec<String>,
+    ) -> Result<Spanned<Expression>, QclError> {
+        trace!("Parsing lambda");
+        self.advance_specific(Token::Arrow)?;
+        let value = self.parse_expression()?;
+        let span = Span::new(self.source.clone(), start, value.span.end);
+        let body = Spanned::new(Statement::Return(Box::new(value)), span.clone());
+        Ok(Spanned::new(
+            Expression::Lambda(parameters, Rc::new(body)),
+            span,
+        ))
+    }
+
+    fn is_lambda_parameters(&self) -> bool {
+        let mut index = self.index + 1;
+        loop {
+            match self.tokens.get(index).map(|token| &token.item) {
+                Some(Token::Identifier(_)) | Some(Token::Comma) => index += 1,
+                Some(Token::RightParen) => break,
+                _ => return false,
+            }
+        }
+        matches!(
+            self.tokens.get(index + 1).map(|token| &token.item),
+            Some(Token::Arrow)
+        )
+    }
+
     fn peek(&self) -> Spanned<Token> {
         match self.index {
             i if i < self.tokens.len() => self.tokens[i].clone(),
@@ -186,6 +1212,19 @@ impl Parser {
         }
     }
 
+    /// Checks whether the next token is the identifier `word`, for words that
+    /// only mean something in one place and can otherwise name variables.
+    fn peek_is_word(&self, word: &str) -> bool {
+        matches!(self.peek().item, Token::Identifier(ref name) if name == word)
+    }
+
+    fn peek_next(&self) -> Spanned<Token> {
+        match self.index + 1 {
+            i if i < self.tokens.len() => self.tokens[i].clone(),
+            _ => self.tokens[self.tokens.len() - 1].clone(),
+        }
+    }
+
     fn advance(&mut self) {
         self.index += 1;
     }
@@ -203,4 +1242,25 @@ impl Parser {
             ))
         }
     }
+
+    fn skip_newlines(&mut self) {
+        while self.peek().item == Token::Newline {
+            self.advance();
+        }
+    }
+
+    fn advance_identifier(&mut self) -> Result<String, QclError> {
+        let token = self.peek();
+        match token.item {
+            Token::Identifier(name) => {
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(QclError::new(
+                QclErrorType::SyntaxError,
+                token.span.clone(),
+                format!("Expected an identifier but found {}", token.item),
+            )),
+        }
+    }
 }
@@ -0,0 +1,146 @@
+use crate::arithmetic;
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::settings::Settings;
+use crate::span::Span;
+use crate::units;
+
+pub fn percentage(value: Object, span: &Span) -> Result<Object, QclError> {
+    if arithmetic::is_number(&value) || units::is_quantity(&value) {
+        Ok(Object::Percentage(Box::new(value)))
+    } else {
+        Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!("Cannot take a percentage of {}", value.type_name()),
+        ))
+    }
+}
+
+/// Computes `percent` percent of `value`.
+pub fn share(
+    value: Object,
+    percent: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    let product = arithmetic::multiply(value, percent, span)?;
+    arithmetic::divide(product, Object::Int(100), settings, span)
+}
+
+/// Turns a percentage into the plain number it stands for, leaving other
+/// values as they are.
+fn fraction(value: Object, settings: &Settings, span: &Span) -> Result<Object, QclError> {
+    match value {
+        Object::Percentage(percent) => share(Object::Int(1), *percent, settings, span),
+        value => Ok(value),
+    }
+}
+
+/// Adds, treating `x + p%` as increasing `x` by `p` percent of itself.
+pub fn add(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    match (left, right) {
+        (Object::Percentage(left), Object::Percentage(right)) => {
+            percentage(arithmetic::add(*left, *right, span)?, span)
+        }
+        (left, Object::Percentage(percent)) => {
+            let increase = share(left.clone(), *percent, settings, span)?;
+            arithmetic::add(left, increase, span)
+        }
+        (left, right) => arithmetic::add(fraction(left, settings, span)?, right, span),
+    }
+}
+
+/// Subtracts, treating `x - p%` as decreasing `x` by `p` percent of itself.
+pub fn subtract(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    match (left, right) {
+        (Object::Percentage(left), Object::Percentage(right)) => {
+            percentage(arithmetic::subtract(*left, *right, span)?, span)
+        }
+        (left, Object::Percentage(percent)) => {
+            let decrease = share(left.clone(), *percent, settings, span)?;
+            arithmetic::subtract(left, decrease, span)
+        }
+        (left, right) => arithmetic::subtract(fraction(left, settings, span)?, right, span),
+    }
+}
+
+pub fn multiply(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    match (left, right) {
+        (Object::Percentage(left), Object::Percentage(right)) => {
+            let left = share(Object::Int(1), *left, settings, span)?;
+            share(left, *right, settings, span)
+        }
+        (value, Object::Percentage(percent)) | (Object::Percentage(percent), value) => {
+            share(value, *percent, settings, span)
+        }
+        (left, right) => arithmetic::multiply(left, right, span),
+    }
+}
+
+pub fn divide(
+    left: Object,
+    right: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    match (left, right) {
+        (Object::Percentage(left), Object::Percentage(right)) => {
+            arithmetic::divide(*left, *right, settings, span)
+        }
+        (Object::Percentage(percent), value) => {
+            percentage(arithmetic::divide(*percent, value, settings, span)?, span)
+        }
+        (value, Object::Percentage(percent)) => {
+            let scaled = arithmetic::multiply(value, Object::Int(100), span)?;
+            arithmetic::divide(scaled, *percent, settings, span)
+        }
+        (left, right) => arithmetic::divide(left, right, settings, span),
+    }
+}
+
+/// Evaluates `p% of x`.
+pub fn of(
+    percent: Object,
+    value: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    match percent {
+        Object::Percentage(percent) => share(value, *percent, settings, span),
+        percent => Err(QclError::new(
+            QclErrorType::TypeError,
+            span.clone(),
+            format!(
+                "Expected a percentage before of but found {}",
+                percent.type_name()
+            ),
+        )),
+    }
+}
+
+/// Evaluates `part as % of whole`.
+pub fn as_percent_of(
+    part: Object,
+    whole: Object,
+    settings: &Settings,
+    span: &Span,
+) -> Result<Object, QclError> {
+    let scaled = arithmetic::multiply(part, Object::Int(100), span)?;
+    percentage(arithmetic::divide(scaled, whole, settings, span)?, span)
+}
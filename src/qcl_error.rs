@@ -2,10 +2,20 @@ use crate::span::Span;
 use colored::Colorize;
 use std::fmt::{self, Debug};
 
-#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, PartialEq)]
 pub enum QclErrorType {
     SyntaxError,
     DivisionByZeroError,
+    UndefinedVariableError,
+    TypeError,
+    ArityError,
+    OverflowError,
+    ValueError,
+    DimensionError,
+    IndexError,
+    KeyError,
+    MatchError,
 }
 
 #[derive(Debug)]
@@ -23,6 +33,11 @@ impl QclError {
             message,
         }
     }
+
+    #[cfg(test)]
+    pub fn error_type(&self) -> &QclErrorType {
+        &self.error_type
+    }
 }
 
 impl fmt::Display for QclError {
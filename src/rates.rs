@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+use std::fs;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Exchange rates keyed by currency code, each giving how much of that
+/// currency one unit of a common base currency buys.
+///
+/// Tables are JSON or TOML files mapping codes to rates, optionally with a
+/// `base` entry naming the currency whose rate is 1. Rates may also sit in a
+/// `rates` table, and entries not named by currency codes are ignored:
+///
+/// ```text
+/// base = "USD"
+/// [rates]
+/// EUR = 0.92
+/// ```
+pub struct Rates {
+    rates: HashMap<String, f64>,
+}
+
+impl Rates {
+    pub fn new() -> Rates {
+        Rates {
+            rates: HashMap::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Rates, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Unable to read rate table {}: {}", path, error))?;
+        Rates::parse(&text, path.ends_with(".json"))
+            .map_err(|error| format!("Invalid rate table {}: {}", path, error))
+    }
+
+    /// Reads the `base` entry, the entries of a `rates` table and any other
+    /// top-level entries named by currency codes, ignoring everything else.
+    fn parse(text: &str, json: bool) -> Result<Rates, String> {
+        let entries = if json {
+            parse_json(text)?
+        } else {
+            parse_toml(text)?
+        };
+        let mut rates = HashMap::new();
+        for (key, value) in entries {
+            match (key.as_str(), value) {
+                ("base", Value::String(code)) => {
+                    rates.insert(expect_code(code)?, 1.0);
+                }
+                ("rates", Value::Table(table)) => {
+                    for (code, value) in table {
+                        insert_rate(&mut rates, code, value)?;
+                    }
+                }
+                (_, value) => insert_rate(&mut rates, key, value)?,
+            }
+        }
+        Ok(Rates { rates })
+    }
+
+    pub fn get(&self, code: &str) -> Option<f64> {
+        self.rates.get(code).copied()
+    }
+}
+
+/// Checks for a currency code, which is three uppercase letters.
+pub fn is_currency_code(name: &str) -> bool {
+    name.len() == 3 && name.chars().all(|ch| ch.is_ascii_uppercase())
+}
+
+fn expect_code(code: String) -> Result<String, String> {
+    if is_currency_code(&code) {
+        Ok(code)
+    } else {
+        Err(format!("{} is not a three letter currency code", code))
+    }
+}
+
+fn insert_rate(rates: &mut HashMap<String, f64>, code: String, value: Value) -> Result<(), String> {
+    if !is_currency_code(&code) {
+        return Ok(());
+    }
+    match value {
+        Value::Number(rate) if rate.is_finite() && rate > 0.0 => {
+            rates.insert(code, rate);
+            Ok(())
+        }
+        Value::Number(rate) => Err(format!("Invalid exchange rate {} for {}", rate, code)),
+        _ => Err(format!("Expected an exchange rate for {}", code)),
+    }
+}
+
+enum Value {
+    Number(f64),
+    String(String),
+    Table(Vec<(String, Value)>),
+    /// Booleans, nulls, arrays and anything else a rate table may hold
+    /// alongside its rates.
+    Other,
+}
+
+/// Reads a JSON document whose top level is an object.
+fn parse_json(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut chars = text.chars().peekable();
+    skip_whitespace(&mut chars);
+    if chars.next() != Some('{') {
+        return Err("Expected an object".to_string());
+    }
+    let entries = parse_json_object(&mut chars)?;
+    skip_whitespace(&mut chars);
+    match chars.next() {
+        Some(ch) => Err(format!("Unexpected character '{}'", ch)),
+        None => Ok(entries),
+    }
+}
+
+/// Reads the members of an object whose `{` has been consumed.
+fn parse_json_object(chars: &mut Peekable<Chars>) -> Result<Vec<(String, Value)>, String> {
+    let mut entries = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Ok(entries);
+    }
+    loop {
+        skip_whitespace(chars);
+        if chars.next() != Some('"') {
+            return Err("Expected a string key".to_string());
+        }
+        let key = parse_string(chars)?;
+        skip_whitespace(chars);
+        if chars.next() != Some(':') {
+            return Err(format!("Expected ':' after \"{}\"", key));
+        }
+        entries.push((key, parse_json_value(chars)?));
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => (),
+            Some('}') => return Ok(entries),
+            _ => return Err("Expected ',' or '}' in object".to_string()),
+        }
+    }
+}
+
+fn parse_json_value(chars: &mut Peekable<Chars>) -> Result<Value, String> {
+    skip_whitespace(chars);
+    match chars.peek() {
+        Some('{') => {
+            chars.next();
+            Ok(Value::Table(parse_json_object(chars)?))
+        }
+        Some('[') => {
+            chars.next();
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Value::Other);
+            }
+            loop {
+                parse_json_value(chars)?;
+                skip_whitespace(chars);
+                match chars.next() {
+                    Some(',') => (),
+                    Some(']') => return Ok(Value::Other),
+                    _ => return Err("Expected ',' or ']' in array".to_string()),
+                }
+            }
+        }
+        Some('"') => {
+            chars.next();
+            Ok(Value::String(parse_string(chars)?))
+        }
+        Some(ch) if ch.is_ascii_digit() || *ch == '-' => Ok(Value::Number(parse_number(chars)?)),
+        Some(ch) if ch.is_ascii_alphabetic() => {
+            let mut word = String::new();
+            while let Some(ch) = chars.next_if(char::is_ascii_alphabetic) {
+                word.push(ch);
+            }
+            match word.as_str() {
+                "true" | "false" | "null" => Ok(Value::Other),
+                _ => Err(format!("Unexpected word \"{}\"", word)),
+            }
+        }
+        Some(ch) => Err(format!("Unexpected character '{}'", ch)),
+        None => Err("Unexpected end of document".to_string()),
+    }
+}
+
+/// Reads `key = value` lines, gathering those under a `[rates]` header into
+/// a table and skipping those under any other header.
+fn parse_toml(text: &str) -> Result<Vec<(String, Value)>, String> {
+    let mut entries = Vec::new();
+    let mut rates = Vec::new();
+    let mut table: Option<String> = None;
+    for line in text.lines() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            table = Some(
+                line.trim_matches(|ch| ch == '[' || ch == ']')
+                    .trim()
+                    .to_string(),
+            );
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Expected key = value but found \"{}\"", line))?;
+        let key = key.trim().trim_matches('"').to_string();
+        let value = value.trim();
+        let value = match value.strip_prefix('"') {
+            Some(value) => Value::String(parse_string(&mut value.chars().peekable())?),
+            None => value
+                .replace('_', "")
+                .parse()
+                .map_or(Value::Other, Value::Number),
+        };
+        match table.as_deref() {
+            None => entries.push((key, value)),
+            Some("rates") => rates.push((key, value)),
+            Some(_) => (),
+        }
+    }
+    if !rates.is_empty() {
+        entries.push(("rates".to_string(), Value::Table(rates)));
+    }
+    Ok(entries)
+}
+
+/// Cuts a `#` comment off a TOML line, keeping any `#` inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..index],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => match chars.next() {
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('r') => string.push('\r'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('u') => {
+                    let digits: String = chars.by_ref().take(4).collect();
+                    let ch = u32::from_str_radix(&digits, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .unwrap_or(char::REPLACEMENT_CHARACTER);
+                    string.push(ch);
+                }
+                Some(ch) => string.push(ch),
+                None => return Err("Unterminated string".to_string()),
+            },
+            Some(ch) => string.push(ch),
+            None => return Err("Unterminated string".to_string()),
+        }
+    }
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Result<f64, String> {
+    let mut number = String::new();
+    while let Some(ch) = chars.next_if(|ch| ch.is_ascii_digit() || "+-.eE_".contains(*ch)) {
+        if ch != '_' {
+            number.push(ch);
+        }
+    }
+    number
+        .parse()
+        .map_err(|_| format!("Invalid number \"{}\"", number))
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_json_rate_dumps() {
+        let text = r#"{
+            "disclaimer": "Usage subject to terms",
+            "timestamp": 1449877801,
+            "live": true,
+            "source": null,
+            "tags": ["daily", {"kind": "fx"}],
+            "base": "USD",
+            "rates": {"EUR": 0.92, "GBP": 0.79, "note": "mid-market"}
+        }"#;
+        let rates = Rates::parse(text, true).unwrap();
+        assert_eq!(rates.get("USD"), Some(1.0));
+        assert_eq!(rates.get("EUR"), Some(0.92));
+        assert_eq!(rates.get("GBP"), Some(0.79));
+        assert_eq!(rates.rates.len(), 3);
+    }
+
+    #[test]
+    fn reads_top_level_json_rates() {
+        let rates = Rates::parse(r#"{"base": "EUR", "USD": 1.087}"#, true).unwrap();
+        assert_eq!(rates.get("EUR"), Some(1.0));
+        assert_eq!(rates.get("USD"), Some(1.087));
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        assert!(Rates::parse(r#"{"EUR": 0.92"#, true).is_err());
+        assert!(Rates::parse(r#"{"EUR": "high"}"#, true).is_err());
+        assert!(Rates::parse(r#"{"EUR": -1}"#, true).is_err());
+        assert!(Rates::parse(r#"{"base": "euro"}"#, true).is_err());
+    }
+
+    #[test]
+    fn reads_toml_rate_tables() {
+        let text = "
+            # Daily rates
+            base = \"EUR\" # the base currency
+            disclaimer = \"#1 rate, \\\"as is\\\" # no warranty\"
+            updated = 2024-01-01
+            live = true
+            CHF = 0.96
+
+            [rates]
+            USD = 1.087
+            JPY = 162_5e-1
+
+            [source]
+            GBP = 12
+        ";
+        let rates = Rates::parse(text, false).unwrap();
+        assert_eq!(rates.get("EUR"), Some(1.0));
+        assert_eq!(rates.get("CHF"), Some(0.96));
+        let entries = parse_toml(text).unwrap();
+        assert!(entries.iter().any(|(key, value)| key == "disclaimer"
+            && matches!(value, Value::String(text) if text == "#1 rate, \"as is\" # no warranty")));
+        assert_eq!(rates.get("USD"), Some(1.087));
+        assert_eq!(rates.get("JPY"), Some(162.5));
+        assert_eq!(rates.get("GBP"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_toml() {
+        assert!(Rates::parse("EUR 0.92", false).is_err());
+        assert!(Rates::parse("[rates]\nEUR = high", false).is_err());
+        assert!(Rates::parse("EUR = 0", false).is_err());
+    }
+}
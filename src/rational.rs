@@ -0,0 +1,126 @@
+use crate::bigint::BigInt;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A fraction kept in lowest terms with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    pub numerator: BigInt,
+    pub denominator: BigInt,
+}
+
+impl Rational {
+    /// Builds a normalized fraction, or returns None for a zero denominator.
+    pub fn new(numerator: BigInt, denominator: BigInt) -> Option<Rational> {
+        if denominator.is_zero() {
+            return None;
+        }
+        let divisor = numerator.gcd(&denominator);
+        let divisor = if denominator.is_negative() {
+            -&divisor
+        } else {
+            divisor
+        };
+        Some(Rational {
+            numerator: numerator.div_rem(&divisor)?.0,
+            denominator: denominator.div_rem(&divisor)?.0,
+        })
+    }
+
+    pub fn from_integer(value: BigInt) -> Rational {
+        Rational {
+            numerator: value,
+            denominator: BigInt::from(1),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == BigInt::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator.to_f64() / self.denominator.to_f64()
+    }
+
+    pub fn negate(&self) -> Rational {
+        Rational {
+            numerator: -&self.numerator,
+            denominator: self.denominator.clone(),
+        }
+    }
+
+    pub fn add(&self, other: &Rational) -> Rational {
+        Rational::new(
+            &(&self.numerator * &other.denominator) + &(&other.numerator * &self.denominator),
+            &self.denominator * &other.denominator,
+        )
+        .expect("Denominators are not zero")
+    }
+
+    pub fn subtract(&self, other: &Rational) -> Rational {
+        Rational::new(
+            &(&self.numerator * &other.denominator) - &(&other.numerator * &self.denominator),
+            &self.denominator * &other.denominator,
+        )
+        .expect("Denominators are not zero")
+    }
+
+    pub fn multiply(&self, other: &Rational) -> Rational {
+        Rational::new(
+            &self.numerator * &other.numerator,
+            &self.denominator * &other.denominator,
+        )
+        .expect("Denominators are not zero")
+    }
+
+    pub fn divide(&self, other: &Rational) -> Option<Rational> {
+        Rational::new(
+            &self.numerator * &other.denominator,
+            &self.denominator * &other.numerator,
+        )
+    }
+
+    pub fn floor(&self) -> BigInt {
+        self.numerator
+            .div_mod_floor(&self.denominator)
+            .expect("Denominator is not zero")
+            .0
+    }
+
+    pub fn pow(&self, exponent: i64) -> Option<Rational> {
+        let magnitude = u32::try_from(exponent.unsigned_abs()).ok()?;
+        let numerator = self.numerator.pow(magnitude);
+        let denominator = self.denominator.pow(magnitude);
+        if exponent < 0 {
+            Rational::new(denominator, numerator)
+        } else {
+            Rational::new(numerator, denominator)
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (&self.numerator * &other.denominator).cmp(&(&other.numerator * &self.denominator))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
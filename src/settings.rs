@@ -0,0 +1,64 @@
+use crate::decimal::RoundingMode;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DivisionMode {
+    Float,
+    Rational,
+}
+
+/// The most digits after the decimal point that `set precision` allows.
+/// Dividing to more would take seconds per operation.
+pub const MAX_PRECISION: u32 = 1000;
+
+/// Script-level options changed with `set <name> = <value>`.
+pub struct Settings {
+    pub division: DivisionMode,
+    pub precision: u32,
+    pub rounding: RoundingMode,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            division: DivisionMode::Float,
+            precision: 28,
+            rounding: RoundingMode::HalfEven,
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match (name, value) {
+            ("division", "float") => self.division = DivisionMode::Float,
+            ("division", "rational") => self.division = DivisionMode::Rational,
+            ("division", _) => {
+                return Err(format!(
+                    "Expected float or rational for division but found {}",
+                    value
+                ))
+            }
+            ("precision", _) => {
+                self.precision = value
+                    .parse()
+                    .ok()
+                    .filter(|precision| *precision <= MAX_PRECISION)
+                    .ok_or_else(|| {
+                        format!(
+                            "Expected a whole number of digits up to {} for precision but found {}",
+                            MAX_PRECISION, value
+                        )
+                    })?
+            }
+            ("rounding", "half_even") => self.rounding = RoundingMode::HalfEven,
+            ("rounding", "half_up") => self.rounding = RoundingMode::HalfUp,
+            ("rounding", "truncate") => self.rounding = RoundingMode::Truncate,
+            ("rounding", _) => {
+                return Err(format!(
+                    "Expected half_even, half_up or truncate for rounding but found {}",
+                    value
+                ))
+            }
+            _ => return Err(format!("Unknown setting {}", name)),
+        }
+        Ok(())
+    }
+}
@@ -23,26 +23,19 @@ impl Span {
 
 impl fmt::Display for Span {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let line_start = self.source[..self.start].matches('\n').count();
-        let line_end = self.source[..self.end].matches('\n').count();
-        let line_start_index = if line_start > 0 {
-            self.source
-                .match_indices('\n')
-                .nth(line_start - 1)
-                .unwrap()
-                .0
-                + 1
-        } else {
-            0
-        };
-        let line = self.source.split('\n').nth(line_start).unwrap();
+        // Spans count characters rather than bytes, so the source is walked
+        // by character to find the line and column.
+        let before: String = self.source.chars().take(self.start).collect();
+        let line_start = before.matches('\n').count();
+        let column = before.chars().rev().take_while(|ch| *ch != '\n').count();
+        let line = self.source.split('\n').nth(line_start).unwrap_or("");
         let prefix = format!("{} | ", line_start);
         write!(
             f,
             "{}{}\n{}{}",
             prefix,
             line,
-            " ".repeat(prefix.len() + self.start - line_start_index),
+            " ".repeat(prefix.len() + column),
             "^".repeat(self.end - self.start + 1).bright_red()
         )
     }
@@ -53,3 +46,39 @@ impl<T> Spanned<T> {
         Spanned { item, span }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Interpreter;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+    use crate::rates::Rates;
+
+    fn caret_column(output: &str) -> Option<usize> {
+        output.lines().nth(1)?.find('^')
+    }
+
+    #[test]
+    fn points_at_characters_after_multibyte_text() {
+        let source = Rc::new("let s = \"€€€€€\"\nprint zz".to_string());
+        let start = source.chars().position(|ch| ch == 'z').unwrap();
+        let output = Span::new(source, start, start + 1).to_string();
+        assert_eq!(output.lines().next(), Some("1 | print zz"));
+        assert_eq!(caret_column(&output), Some("1 | print ".len()));
+    }
+
+    #[test]
+    fn reports_errors_after_multibyte_strings() {
+        let source = "{\n    let s = \"€€€€€\"\n    print zz\n}\n".to_string();
+        let tokens = Lexer::new(source.clone()).lex().unwrap();
+        let ast = Parser::new(source, tokens).parse().unwrap();
+        let error = Interpreter::new(ast, Rates::new())
+            .interpret()
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error.lines().next(), Some("2 |     print zz"));
+        assert_eq!(caret_column(&error), Some("2 |     print ".len()));
+        assert!(error.contains("Variable zz is not defined"));
+    }
+}
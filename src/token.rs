@@ -1,3 +1,4 @@
+use crate::format::Format;
 use std::fmt;
 
 #[derive(Debug, PartialEq, Clone)]
@@ -7,35 +8,139 @@ pub enum Token {
     Newline,
     // Keywords
     Print,
+    Let,
+    Fn,
+    Return,
+    True,
+    False,
+    And,
+    Or,
+    Not,
+    If,
+    Then,
+    Else,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    Set,
+    To,
+    As,
+    Match,
     // Literals
     Number(String),
+    Decimal(String),
+    Imaginary(String),
+    String(String),
+    InterpolatedString(Vec<StringPart>),
+    Date(String),
+    Time(String),
     Identifier(String),
     // Symbols
     Plus,
     Minus,
     Star,
     Slash,
+    DoubleSlash,
+    Percent,
+    Percentage,
+    Caret,
+    Equal,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Comma,
+    DotDot,
+    DotDotEqual,
+    Dot,
+    Colon,
+    Arrow,
+    FatArrow,
     LeftParen,
     RightParen,
     LeftCurly,
+    LeftBracket,
+    RightBracket,
     RightCurly,
 }
 
+/// A piece of an interpolated string: either literal text or the characters
+/// from `start` up to `end` of the source, holding an expression to format.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StringPart {
+    Text(String),
+    Value {
+        start: usize,
+        end: usize,
+        format: Format,
+    },
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let string = match &self {
             Token::End => "the end".to_string(),
             Token::Newline => "a newline".to_string(),
             Token::Print => "the print keyword".to_string(),
+            Token::Let => "the let keyword".to_string(),
+            Token::Fn => "the fn keyword".to_string(),
+            Token::Return => "the return keyword".to_string(),
+            Token::True => "the true keyword".to_string(),
+            Token::False => "the false keyword".to_string(),
+            Token::And => "the and keyword".to_string(),
+            Token::Or => "the or keyword".to_string(),
+            Token::Not => "the not keyword".to_string(),
+            Token::If => "the if keyword".to_string(),
+            Token::Then => "the then keyword".to_string(),
+            Token::Else => "the else keyword".to_string(),
+            Token::While => "the while keyword".to_string(),
+            Token::For => "the for keyword".to_string(),
+            Token::In => "the in keyword".to_string(),
+            Token::Break => "the break keyword".to_string(),
+            Token::Continue => "the continue keyword".to_string(),
+            Token::Set => "the set keyword".to_string(),
+            Token::To => "the to keyword".to_string(),
+            Token::As => "the as keyword".to_string(),
+            Token::Match => "the match keyword".to_string(),
             Token::Number(number) => format!("the number {}", number),
+            Token::Decimal(number) => format!("the decimal {}d", number),
+            Token::Imaginary(number) => format!("the imaginary number {}i", number),
+            Token::Date(date) => format!("the date {}", date),
+            Token::Time(time) => format!("the time {}", time),
+            Token::String(string) => format!("the string \"{}\"", string),
+            Token::InterpolatedString(_) => "an interpolated string".to_string(),
             Token::Identifier(name) => format!("the identifier {}", name),
             Token::Plus => "+".to_string(),
             Token::Minus => "-".to_string(),
             Token::Star => "*".to_string(),
             Token::Slash => "/".to_string(),
+            Token::DoubleSlash => "//".to_string(),
+            Token::Percent => "%".to_string(),
+            Token::Percentage => "%".to_string(),
+            Token::Caret => "^".to_string(),
+            Token::Equal => "=".to_string(),
+            Token::EqualEqual => "==".to_string(),
+            Token::BangEqual => "!=".to_string(),
+            Token::Less => "<".to_string(),
+            Token::LessEqual => "<=".to_string(),
+            Token::Greater => ">".to_string(),
+            Token::GreaterEqual => ">=".to_string(),
+            Token::Comma => ",".to_string(),
+            Token::DotDot => "..".to_string(),
+            Token::DotDotEqual => "..=".to_string(),
+            Token::Dot => ".".to_string(),
+            Token::Colon => ":".to_string(),
+            Token::Arrow => "->".to_string(),
+            Token::FatArrow => "=>".to_string(),
             Token::LeftParen => "(".to_string(),
             Token::RightParen => ")".to_string(),
             Token::LeftCurly => "{".to_string(),
+            Token::LeftBracket => "[".to_string(),
+            Token::RightBracket => "]".to_string(),
             Token::RightCurly => "}".to_string(),
         };
         write!(f, "{}", string)
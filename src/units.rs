@@ -0,0 +1,600 @@
+use crate::arithmetic;
+use crate::object::Object;
+use crate::qcl_error::{QclError, QclErrorType};
+use crate::rates::{self, Rates};
+use crate::span::Span;
+use std::cmp::Ordering;
+use std::fmt;
+use std::rc::Rc;
+
+/// Exponents of the SI base dimensions: length, mass, time, current,
+/// temperature, amount of substance and luminous intensity, followed by
+/// currency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dimension([i32; 8]);
+
+const NONE: [i32; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
+const LENGTH: [i32; 8] = [1, 0, 0, 0, 0, 0, 0, 0];
+const MASS: [i32; 8] = [0, 1, 0, 0, 0, 0, 0, 0];
+const TIME: [i32; 8] = [0, 0, 1, 0, 0, 0, 0, 0];
+const CURRENT: [i32; 8] = [0, 0, 0, 1, 0, 0, 0, 0];
+const TEMPERATURE: [i32; 8] = [0, 0, 0, 0, 1, 0, 0, 0];
+const AMOUNT: [i32; 8] = [0, 0, 0, 0, 0, 1, 0, 0];
+const LUMINOSITY: [i32; 8] = [0, 0, 0, 0, 0, 0, 1, 0];
+const CURRENCY: [i32; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+const SPEED: [i32; 8] = [1, 0, -1, 0, 0, 0, 0, 0];
+const VOLUME: [i32; 8] = [3, 0, 0, 0, 0, 0, 0, 0];
+const FREQUENCY: [i32; 8] = [0, 0, -1, 0, 0, 0, 0, 0];
+const FORCE: [i32; 8] = [1, 1, -2, 0, 0, 0, 0, 0];
+const ENERGY: [i32; 8] = [2, 1, -2, 0, 0, 0, 0, 0];
+const POWER: [i32; 8] = [2, 1, -3, 0, 0, 0, 0, 0];
+const PRESSURE: [i32; 8] = [-1, 1, -2, 0, 0, 0, 0, 0];
+const VOLTAGE: [i32; 8] = [2, 1, -3, -1, 0, 0, 0, 0];
+
+impl Dimension {
+    fn is_none(&self) -> bool {
+        self.0 == NONE
+    }
+
+    /// Adds `exponent` times `other` to this dimension.
+    fn combine(&self, other: &Dimension, exponent: i32) -> Dimension {
+        let mut result = self.0;
+        for (result, other) in result.iter_mut().zip(other.0) {
+            *result += other * exponent;
+        }
+        Dimension(result)
+    }
+}
+
+struct Definition {
+    name: &'static str,
+    factor: f64,
+    offset: f64,
+    dimension: [i32; 8],
+    prefixable: bool,
+}
+
+const fn prefixable(name: &'static str, factor: f64, dimension: [i32; 8]) -> Definition {
+    Definition {
+        name,
+        factor,
+        offset: 0.0,
+        dimension,
+        prefixable: true,
+    }
+}
+
+const fn plain(name: &'static str, factor: f64, dimension: [i32; 8]) -> Definition {
+    Definition {
+        name,
+        factor,
+        offset: 0.0,
+        dimension,
+        prefixable: false,
+    }
+}
+
+/// A temperature scale whose zero is `offset` kelvin.
+const fn temperature(name: &'static str, factor: f64, offset: f64) -> Definition {
+    Definition {
+        name,
+        factor,
+        offset,
+        dimension: TEMPERATURE,
+        prefixable: false,
+    }
+}
+
+static DEFINITIONS: &[Definition] = &[
+    prefixable("m", 1.0, LENGTH),
+    prefixable("g", 0.001, MASS),
+    prefixable("s", 1.0, TIME),
+    prefixable("A", 1.0, CURRENT),
+    prefixable("K", 1.0, TEMPERATURE),
+    prefixable("mol", 1.0, AMOUNT),
+    prefixable("cd", 1.0, LUMINOSITY),
+    prefixable("L", 0.001, VOLUME),
+    prefixable("Hz", 1.0, FREQUENCY),
+    prefixable("N", 1.0, FORCE),
+    prefixable("J", 1.0, ENERGY),
+    prefixable("W", 1.0, POWER),
+    prefixable("Wh", 3600.0, ENERGY),
+    prefixable("Pa", 1.0, PRESSURE),
+    prefixable("V", 1.0, VOLTAGE),
+    plain("second", 1.0, TIME),
+    plain("seconds", 1.0, TIME),
+    plain("min", 60.0, TIME),
+    plain("minute", 60.0, TIME),
+    plain("minutes", 60.0, TIME),
+    plain("h", 3600.0, TIME),
+    plain("hour", 3600.0, TIME),
+    plain("hours", 3600.0, TIME),
+    plain("day", 86400.0, TIME),
+    plain("days", 86400.0, TIME),
+    plain("week", 604800.0, TIME),
+    plain("weeks", 604800.0, TIME),
+    plain("inch", 0.0254, LENGTH),
+    plain("ft", 0.3048, LENGTH),
+    plain("yd", 0.9144, LENGTH),
+    plain("mi", 1609.344, LENGTH),
+    plain("mph", 0.44704, SPEED),
+    plain("lb", 0.45359237, MASS),
+    plain("oz", 0.028349523125, MASS),
+    plain("gal", 0.003785411784, VOLUME),
+    temperature("C", 1.0, 273.15),
+    temperature("F", 5.0 / 9.0, 459.67 * 5.0 / 9.0),
+];
+
+static PREFIXES: &[(&str, f64)] = &[
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+];
+
+/// Named units that products and quotients of other units simplify to.
+const SIMPLIFIED: [&str; 5] = ["N", "J", "W", "Pa", "V"];
+
+/// A product of named units raised to integer powers, together with its
+/// scale relative to SI base units. Only a lone temperature scale has an
+/// offset, since in compound units temperatures are differences.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    terms: Vec<(String, i32)>,
+    factor: f64,
+    offset: f64,
+    dimension: Dimension,
+}
+
+impl Unit {
+    /// Checks whether `name` can name a unit. Currency codes count even
+    /// before a rate table is loaded, since tables are loaded at run time.
+    pub fn is_unit(name: &str) -> bool {
+        Unit::lookup(name).is_some() || rates::is_currency_code(name)
+    }
+
+    /// Finds a unit by name, allowing an SI prefix on units that take one.
+    fn lookup(name: &str) -> Option<Unit> {
+        let find = |name: &str| {
+            DEFINITIONS
+                .iter()
+                .find(|definition| definition.name == name)
+        };
+        let (scale, definition) = match find(name) {
+            Some(definition) => (1.0, definition),
+            None => PREFIXES.iter().find_map(|(prefix, scale)| {
+                let definition = find(name.strip_prefix(prefix)?)?;
+                definition.prefixable.then_some((*scale, definition))
+            })?,
+        };
+        Some(Unit {
+            terms: vec![(name.to_string(), 1)],
+            factor: scale * definition.factor,
+            offset: definition.offset,
+            dimension: Dimension(definition.dimension),
+        })
+    }
+
+    /// Converts one unit of a currency to the base currency of the table.
+    fn currency(code: &str, rates: &Rates) -> Result<Unit, String> {
+        match rates.get(code) {
+            Some(rate) => Ok(Unit {
+                terms: vec![(code.to_string(), 1)],
+                factor: 1.0 / rate,
+                offset: 0.0,
+                dimension: Dimension(CURRENCY),
+            }),
+            None => Err(format!("There is no exchange rate for {}", code)),
+        }
+    }
+
+    /// Builds the unit for a product of named units, looking currencies up
+    /// in `rates`.
+    pub fn resolve(terms: &[(String, i32)], rates: &Rates) -> Result<Unit, String> {
+        let lookup = |name: &str| match Unit::lookup(name) {
+            Some(unit) => Ok(unit),
+            None if rates::is_currency_code(name) => Unit::currency(name, rates),
+            None => Err(format!("Unknown unit {}", name)),
+        };
+        if let [(name, 1)] = terms {
+            return lookup(name);
+        }
+        let mut unit = Unit::none();
+        for (name, exponent) in terms {
+            unit = unit.multiply(&lookup(name)?.pow(*exponent));
+        }
+        Ok(unit)
+    }
+
+    /// Lists the currencies in this unit, which must match for amounts to be
+    /// combined without an explicit conversion.
+    fn currencies(&self) -> Vec<&(String, i32)> {
+        self.terms
+            .iter()
+            .filter(|(name, _)| Unit::lookup(name).is_none())
+            .collect()
+    }
+
+    fn none() -> Unit {
+        Unit {
+            terms: Vec::new(),
+            factor: 1.0,
+            offset: 0.0,
+            dimension: Dimension(NONE),
+        }
+    }
+
+    fn pow(&self, exponent: i32) -> Unit {
+        Unit {
+            terms: self
+                .terms
+                .iter()
+                .map(|(name, power)| (name.clone(), power * exponent))
+                .filter(|(_, power)| *power != 0)
+                .collect(),
+            factor: self.factor.powi(exponent),
+            offset: 0.0,
+            dimension: Dimension(NONE).combine(&self.dimension, exponent),
+        }
+    }
+
+    fn multiply(&self, other: &Unit) -> Unit {
+        let mut terms = self.terms.clone();
+        for (name, exponent) in &other.terms {
+            match terms.iter_mut().find(|(term, _)| term == name) {
+                Some((_, power)) => *power += exponent,
+                None => terms.push((name.clone(), *exponent)),
+            }
+        }
+        terms.retain(|(_, power)| *power != 0);
+        Unit {
+            terms,
+            factor: self.factor * other.factor,
+            offset: 0.0,
+            dimension: self.dimension.combine(&other.dimension, 1),
+        }
+    }
+
+    /// Replaces a compound unit by the named unit of the same dimension, if
+    /// there is one, returning the factor to rescale values by.
+    fn simplified(self) -> (f64, Unit) {
+        if self.terms.len() > 1 {
+            let named = SIMPLIFIED
+                .iter()
+                .filter_map(|name| Unit::lookup(name))
+                .find(|unit| unit.dimension == self.dimension);
+            if let Some(named) = named {
+                return (self.factor / named.factor, named);
+            }
+        }
+        (1.0, self)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let format_terms = |positive: bool| {
+            self.terms
+                .iter()
+                .filter(|(_, exponent)| (*exponent > 0) == positive)
+                .map(|(name, exponent)| match exponent.abs() {
+                    1 => name.clone(),
+                    exponent => format!("{}^{}", name, exponent),
+                })
+                .collect::<Vec<_>>()
+        };
+        let numerator = format_terms(true);
+        let denominator = format_terms(false);
+        if numerator.is_empty() {
+            let inverse: Vec<String> = self
+                .terms
+                .iter()
+                .map(|(name, exponent)| format!("{}^{}", name, exponent))
+                .collect();
+            return write!(f, "{}", inverse.join("*"));
+        }
+        write!(f, "{}", numerator.join("*"))?;
+        match denominator.len() {
+            0 => Ok(()),
+            1 => write!(f, "/{}", denominator[0]),
+            _ => write!(f, "/({})", denominator.join("*")),
+        }
+    }
+}
+
+/// A number with a unit, stored in that unit rather than in SI base units so
+/// that values print the way they were written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    fn in_base_units(&self) -> f64 {
+        self.value * self.unit.factor + self.unit.offset
+    }
+
+    fn in_unit(&self, unit: &Unit) -> f64 {
+        (self.in_base_units() - unit.offset) / unit.factor
+    }
+
+    /// Converts this quantity as a difference, by the factor alone. Adding
+    /// `5 K` to `20 C` raises the temperature by five degrees rather than
+    /// adding an absolute temperature of -268.15 C.
+    fn as_difference_in(&self, unit: &Unit) -> f64 {
+        self.value * self.unit.factor / unit.factor
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Rounding to 15 significant digits hides the noise that converting
+        // between units adds to values.
+        let value: f64 = format!("{:.14e}", self.value).parse().unwrap_or(self.value);
+        write!(f, "{} {}", value, self.unit)
+    }
+}
+
+/// Wraps a value, dropping the unit when its dimensions cancel out.
+pub fn quantity(value: f64, unit: Unit) -> Object {
+    if unit.dimension.is_none() {
+        Object::Float(value * unit.factor)
+    } else {
+        Object::Quantity(Rc::new(Quantity { value, unit }))
+    }
+}
+
+/// Builds a duration of `value` in the named unit of time.
+pub fn duration(value: f64, unit: &str) -> Object {
+    quantity(value, Unit::lookup(unit).expect("Unit of time exists"))
+}
+
+/// Returns the length of a duration in seconds, or None for other values.
+pub fn seconds(value: &Object) -> Option<f64> {
+    match value {
+        Object::Quantity(value) if value.unit.dimension == Dimension(TIME) => {
+            Some(value.in_base_units())
+        }
+        _ => None,
+    }
+}
+
+pub fn is_quantity(value: &Object) -> bool {
+    matches!(value, Object::Quantity(_))
+}
+
+pub fn convert(value: Object, unit: Unit, span: &Span) -> Result<Object, QclError> {
+    match &value {
+        Object::Quantity(value) if value.unit.dimension == unit.dimension => {
+            Ok(quantity(value.in_unit(&unit), unit))
+        }
+        _ => Err(QclError::new(
+            QclErrorType::DimensionError,
+            span.clone(),
+            format!("Cannot convert {} to {}", describe(&value), unit),
+        )),
+    }
+}
+
+pub fn negate(value: &Quantity) -> Object {
+    quantity(-value.value, value.unit.clone())
+}
+
+pub fn add(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    let (left, right) = expect_compatible("add", &left, &right, span)?;
+    Ok(quantity(
+        left.value + right.as_difference_in(&left.unit),
+        left.unit.clone(),
+    ))
+}
+
+pub fn subtract(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    let (left, right) = expect_compatible("subtract", &left, &right, span)?;
+    Ok(quantity(
+        left.value - right.as_difference_in(&left.unit),
+        left.unit.clone(),
+    ))
+}
+
+pub fn multiply(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    match (&left, &right) {
+        (Object::Quantity(left), Object::Quantity(right)) => {
+            let (scale, unit) = left.unit.multiply(&right.unit).simplified();
+            Ok(quantity(left.value * right.value * scale, unit))
+        }
+        (Object::Quantity(value), number) | (number, Object::Quantity(value)) => {
+            let number = expect_real("multiply", &left, &right, number, span)?;
+            Ok(quantity(value.value * number, value.unit.clone()))
+        }
+        _ => Err(arithmetic::operand_error("multiply", &left, &right, span)),
+    }
+}
+
+pub fn divide(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    if arithmetic::is_zero(&right)
+        || matches!(&right, Object::Quantity(right) if right.value == 0.0)
+    {
+        return Err(QclError::new(
+            QclErrorType::DivisionByZeroError,
+            span.clone(),
+            ":(".to_string(),
+        ));
+    }
+    match (&left, &right) {
+        (Object::Quantity(left), Object::Quantity(right)) => {
+            let (scale, unit) = left.unit.multiply(&right.unit.pow(-1)).simplified();
+            Ok(quantity(left.value / right.value * scale, unit))
+        }
+        (Object::Quantity(value), number) => {
+            let number = expect_real("divide", &left, &right, number, span)?;
+            Ok(quantity(value.value / number, value.unit.clone()))
+        }
+        (number, Object::Quantity(value)) => {
+            let number = expect_real("divide", &left, &right, number, span)?;
+            Ok(quantity(number / value.value, value.unit.pow(-1)))
+        }
+        _ => Err(arithmetic::operand_error("divide", &left, &right, span)),
+    }
+}
+
+pub fn power(left: Object, right: Object, span: &Span) -> Result<Object, QclError> {
+    let (Object::Quantity(base), Object::Int(exponent)) = (&left, &right) else {
+        return Err(QclError::new(
+            QclErrorType::DimensionError,
+            span.clone(),
+            format!(
+                "Cannot exponentiate {} and {}, quantities can only be raised to integer powers",
+                describe(&left),
+                describe(&right)
+            ),
+        ));
+    };
+    let exponent = i32::try_from(*exponent).map_err(|_| {
+        QclError::new(
+            QclErrorType::OverflowError,
+            span.clone(),
+            "Exponent is too large".to_string(),
+        )
+    })?;
+    Ok(quantity(base.value.powi(exponent), base.unit.pow(exponent)))
+}
+
+/// Orders two quantities of the same dimension, or returns None otherwise.
+pub fn compare(left: &Object, right: &Object) -> Option<Ordering> {
+    match (left, right) {
+        (Object::Quantity(left), Object::Quantity(right)) if is_compatible(left, right) => {
+            // Values converted between units rarely agree to the last bit, so
+            // quantities within a relative 1e-12 of each other are equal.
+            let (left, right) = (left.in_base_units(), right.in_base_units());
+            if (left - right).abs() <= 1e-12 * left.abs().max(right.abs()) {
+                Some(Ordering::Equal)
+            } else {
+                left.partial_cmp(&right)
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_compatible(left: &Quantity, right: &Quantity) -> bool {
+    left.unit.dimension == right.unit.dimension && left.unit.currencies() == right.unit.currencies()
+}
+
+fn expect_compatible<'a>(
+    operation: &str,
+    left: &'a Object,
+    right: &'a Object,
+    span: &Span,
+) -> Result<(&'a Quantity, &'a Quantity), QclError> {
+    match (left, right) {
+        (Object::Quantity(left), Object::Quantity(right)) if is_compatible(left, right) => {
+            Ok((left, right))
+        }
+        _ => Err(dimension_error(operation, left, right, span)),
+    }
+}
+
+fn expect_real(
+    operation: &str,
+    left: &Object,
+    right: &Object,
+    number: &Object,
+    span: &Span,
+) -> Result<f64, QclError> {
+    match number {
+        Object::Complex(_) => None,
+        number => arithmetic::to_float(number),
+    }
+    .ok_or_else(|| arithmetic::operand_error(operation, left, right, span))
+}
+
+pub fn dimension_error(operation: &str, left: &Object, right: &Object, span: &Span) -> QclError {
+    if !(arithmetic::is_number(left) || is_quantity(left))
+        || !(arithmetic::is_number(right) || is_quantity(right))
+    {
+        return arithmetic::operand_error(operation, left, right, span);
+    }
+    if let (Object::Quantity(left), Object::Quantity(right)) = (left, right) {
+        if left.unit.dimension == right.unit.dimension {
+            return QclError::new(
+                QclErrorType::DimensionError,
+                span.clone(),
+                format!(
+                    "Cannot {} {} and {} without converting between currencies first",
+                    operation, left.unit, right.unit
+                ),
+            );
+        }
+    }
+    QclError::new(
+        QclErrorType::DimensionError,
+        span.clone(),
+        format!(
+            "Cannot {} {} and {}",
+            operation,
+            describe(left),
+            describe(right)
+        ),
+    )
+}
+
+fn describe(value: &Object) -> String {
+    match value {
+        Object::Quantity(quantity) => quantity.unit.to_string(),
+        value if arithmetic::is_number(value) => "a dimensionless number".to_string(),
+        value => value.type_name().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temperature(value: f64, unit: &str) -> Object {
+        quantity(value, Unit::lookup(unit).unwrap())
+    }
+
+    fn value_in(object: Object, unit: &str) -> f64 {
+        match object {
+            Object::Quantity(quantity) => {
+                assert_eq!(quantity.unit.to_string(), unit);
+                quantity.value
+            }
+            object => panic!("Expected a quantity but found {}", object),
+        }
+    }
+
+    #[test]
+    fn converts_temperatures_between_scales() {
+        let span = Span::new(Rc::new(String::new()), 0, 0);
+        let celsius = convert(temperature(72.0, "F"), Unit::lookup("C").unwrap(), &span).unwrap();
+        assert!((value_in(celsius, "C") - 22.222222222222).abs() < 1e-9);
+    }
+
+    #[test]
+    fn adds_temperatures_on_other_scales_as_differences() {
+        let span = Span::new(Rc::new(String::new()), 0, 0);
+        let sum = add(temperature(20.0, "C"), temperature(5.0, "K"), &span).unwrap();
+        assert!((value_in(sum, "C") - 25.0).abs() < 1e-9);
+        let difference = subtract(temperature(20.0, "C"), temperature(9.0, "F"), &span).unwrap();
+        assert!((value_in(difference, "C") - 15.0).abs() < 1e-9);
+        let sum = add(temperature(20.0, "C"), temperature(20.0, "C"), &span).unwrap();
+        assert!((value_in(sum, "C") - 40.0).abs() < 1e-9);
+    }
+}